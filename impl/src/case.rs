@@ -0,0 +1,87 @@
+//! Serde's `rename_all` conventions, applied to default tag names.
+
+use syn::{Error, LitStr, Result};
+
+#[derive(Copy, Clone, PartialEq)]
+pub enum RenameRule {
+    Lower,
+    Upper,
+    Pascal,
+    Camel,
+    Snake,
+    ScreamingSnake,
+    Kebab,
+    ScreamingKebab,
+}
+
+use self::RenameRule::*;
+
+impl RenameRule {
+    pub const ALL: [RenameRule; 8] = [
+        Lower,
+        Upper,
+        Pascal,
+        Camel,
+        Snake,
+        ScreamingSnake,
+        Kebab,
+        ScreamingKebab,
+    ];
+
+    pub fn from_lit(lit: &LitStr) -> Result<Self> {
+        let rule = match lit.value().as_str() {
+            "lowercase" => Lower,
+            "UPPERCASE" => Upper,
+            "PascalCase" => Pascal,
+            "camelCase" => Camel,
+            "snake_case" => Snake,
+            "SCREAMING_SNAKE_CASE" => ScreamingSnake,
+            "kebab-case" => Kebab,
+            "SCREAMING-KEBAB-CASE" => ScreamingKebab,
+            _ => {
+                let msg = "unknown rename rule, expected one of \"lowercase\", \"UPPERCASE\", \
+                           \"PascalCase\", \"camelCase\", \"snake_case\", \"SCREAMING_SNAKE_CASE\", \
+                           \"kebab-case\", \"SCREAMING-KEBAB-CASE\"";
+                return Err(Error::new(lit.span(), msg));
+            }
+        };
+        Ok(rule)
+    }
+
+    pub fn index(self) -> usize {
+        RenameRule::ALL
+            .iter()
+            .position(|rule| *rule == self)
+            .unwrap()
+    }
+
+    // Type names are PascalCase, same as the enum variants serde applies its
+    // rename rules to.
+    pub fn apply(self, name: &str) -> String {
+        match self {
+            Pascal => name.to_owned(),
+            Lower => name.to_ascii_lowercase(),
+            Upper => name.to_ascii_uppercase(),
+            Camel => {
+                let mut chars = name.chars();
+                match chars.next() {
+                    Some(first) => first.to_ascii_lowercase().to_string() + chars.as_str(),
+                    None => String::new(),
+                }
+            }
+            Snake => {
+                let mut snake = String::new();
+                for (i, ch) in name.char_indices() {
+                    if i > 0 && ch.is_uppercase() {
+                        snake.push('_');
+                    }
+                    snake.push(ch.to_ascii_lowercase());
+                }
+                snake
+            }
+            ScreamingSnake => Snake.apply(name).to_ascii_uppercase(),
+            Kebab => Snake.apply(name).replace('_', "-"),
+            ScreamingKebab => ScreamingSnake.apply(name).replace('_', "-"),
+        }
+    }
+}
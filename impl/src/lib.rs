@@ -6,11 +6,13 @@
     clippy::too_many_lines
 )]
 
+mod case;
 mod parse;
+mod register;
 mod tagged_impl;
 mod tagged_trait;
 
-use crate::parse::{ImplArgs, Input, TraitArgs};
+use crate::parse::{ImplArgs, Input, RegisterInput, TraitArgs};
 use proc_macro::TokenStream;
 use proc_macro2::{Ident, Span};
 use quote::{ToTokens, TokenStreamExt as _};
@@ -43,6 +45,28 @@ pub fn deserialize(args: TokenStream, input: TokenStream) -> TokenStream {
     expand(args, input, Mode { ser, de })
 }
 
+/// Populate the registry of a trait declared with
+/// `#[typetag::serde(registry = "manual")]`.
+///
+/// ```ignore
+/// typetag::register!(dyn WebEvent => [PageLoad, Click]);
+/// ```
+///
+/// Each listed type must have a `#[typetag::serde(registry = "manual")]` or
+/// `#[typetag::deserialize(registry = "manual")]` impl of the trait. This must
+/// be invoked exactly once per trait, in the crate that defines the trait, and
+/// is the only source of registrations that deserialization consults for that
+/// trait.
+///
+/// ```ignore
+/// typetag::register!(crate = "facade::typetag", dyn WebEvent => [PageLoad, Click]);
+/// ```
+#[proc_macro]
+pub fn register(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as RegisterInput);
+    TokenStream::from(register::expand(input))
+}
+
 fn expand(args: TokenStream, input: TokenStream, mode: Mode) -> TokenStream {
     let input = parse_macro_input!(input as Input);
 
@@ -1,8 +1,12 @@
+use crate::case::RenameRule;
+use proc_macro2::{Ident, TokenStream};
 use quote::quote;
 use syn::parse::{Parse, ParseStream, Result};
+use syn::punctuated::Punctuated;
 use syn::{
-    Attribute, Error, Expr, Generics, ImplItem, ItemImpl, ItemTrait, LitStr, Token, TraitItem,
-    Type, TypeParamBound, Visibility, WherePredicate,
+    bracketed, parenthesized, parse_quote, token, Attribute, Error, Expr, Generics, ImplItem,
+    ItemImpl, ItemTrait, LitStr, Path, PathArguments, Token, TraitItem, Type, TypeParamBound,
+    Visibility, WherePredicate,
 };
 
 mod kw {
@@ -11,9 +15,58 @@ mod kw {
     syn::custom_keyword!(default_variant);
     syn::custom_keyword!(deny_unknown_fields);
     syn::custom_keyword!(name);
+    syn::custom_keyword!(registry);
+    syn::custom_keyword!(instantiate);
+    syn::custom_keyword!(instances);
+    syn::custom_keyword!(alias);
+    syn::custom_keyword!(unknown);
+    syn::custom_keyword!(rename_all);
+    syn::custom_keyword!(namespace);
+    syn::custom_keyword!(context);
+    syn::custom_keyword!(id);
+    syn::custom_keyword!(untagged);
+    syn::custom_keyword!(priority);
+    syn::custom_keyword!(repr);
+    syn::custom_keyword!(no_suggestions);
+    syn::custom_keyword!(unit_shorthand);
+    syn::custom_keyword!(limits);
+    syn::custom_keyword!(bytes);
+    syn::custom_keyword!(elements);
+    syn::custom_keyword!(depth);
+    syn::custom_keyword!(recursion);
 }
 
-pub enum TraitArgs {
+pub struct TraitArgs {
+    pub tagging: Tagging,
+    pub registry: Registry,
+    pub instantiate: Vec<Path>,
+    pub unknown: Option<Type>,
+    pub rename_all: Option<RenameRule>,
+    pub namespace: Option<Namespace>,
+    pub suggestions: bool,
+    pub unit_shorthand: bool,
+    pub limits: Limits,
+    pub crate_path: Path,
+}
+
+// Bounds on content buffered before the tag and on how deeply trait objects
+// nest, each unlimited if absent.
+#[derive(Default)]
+pub struct Limits {
+    pub bytes: Option<Expr>,
+    pub elements: Option<Expr>,
+    pub depth: Option<Expr>,
+    pub recursion: Option<Expr>,
+}
+
+// What default tag names are qualified with.
+#[derive(Copy, Clone)]
+pub enum Namespace {
+    Crate,
+    Module,
+}
+
+pub enum Tagging {
     External,
     Internal {
         tag: LitStr,
@@ -25,10 +78,54 @@ pub enum TraitArgs {
         default_variant: Option<LitStr>,
         deny_unknown_fields: bool,
     },
+    Untagged,
+    Enum,
+}
+
+pub enum Registry {
+    Inventory,
+    Manual,
+}
+
+// registry = "manual"
+impl Parse for Registry {
+    fn parse(input: ParseStream) -> Result<Self> {
+        input.parse::<kw::registry>()?;
+        input.parse::<Token![=]>()?;
+        let lit: LitStr = input.parse()?;
+        match lit.value().as_str() {
+            "inventory" => Ok(Registry::Inventory),
+            "manual" => Ok(Registry::Manual),
+            _ => {
+                let msg = "expected \"inventory\" or \"manual\"";
+                Err(Error::new(lit.span(), msg))
+            }
+        }
+    }
 }
 
 pub struct ImplArgs {
     pub name: Option<Expr>,
+    pub aliases: Vec<Expr>,
+    pub id: Option<Expr>,
+    pub priority: Option<Expr>,
+    pub instances: Vec<Instance>,
+    pub unknown: bool,
+    pub context: Option<Type>,
+    pub registry: Registry,
+    pub crate_path: Path,
+}
+
+pub struct Instance {
+    pub ty: Type,
+    pub name: Expr,
+    pub id: Option<Expr>,
+}
+
+pub struct RegisterInput {
+    pub crate_path: Path,
+    pub object: Type,
+    pub types: Punctuated<Type, Token![,]>,
 }
 
 pub enum Input {
@@ -53,14 +150,6 @@ impl Parse for Input {
                     let span = quote!(#const_token #semi_token);
                     let msg = "typetag trait with associated const is not supported yet";
                     return Err(Error::new_spanned(span, msg));
-                } else if let TraitItem::Type(assoc) = assoc {
-                    if !is_self_sized(&assoc.generics) {
-                        let type_token = assoc.type_token;
-                        let semi_token = assoc.semi_token;
-                        let span = quote!(#type_token #semi_token);
-                        let msg = "typetag trait with associated type is not supported yet";
-                        return Err(Error::new_spanned(span, msg));
-                    }
                 }
             }
             attrs.extend(item.attrs);
@@ -99,93 +188,373 @@ impl Parse for Input {
 // #[typetag::serde(tag = "type", content = "content")]
 // #[typetag::serde(tag = "type", content = "content", deny_unknown_fields)]
 // #[typetag::serde(tag = "type", content = "content", default_variant = "default")]
+// #[typetag::serde(registry = "manual")]
+// #[typetag::serde(instantiate(Trait<A>, Trait<B>))]
+// #[typetag::serde(tag = "type", unknown = UnknownType)]
+// #[typetag::serde(rename_all = "snake_case")]
+// #[typetag::serde(namespace = "module")]
+// #[typetag::serde(untagged)]
+// #[typetag::serde(repr = "enum")]
+// #[typetag::serde(no_suggestions)]
+// #[typetag::serde(tag = "type", unit_shorthand)]
+// #[typetag::serde(tag = "type", limits(bytes = 65536, elements = 1024, depth = 16))]
+// #[typetag::serde(crate = "facade::typetag")]
 impl Parse for TraitArgs {
     fn parse(input: ParseStream) -> Result<Self> {
-        if input.is_empty() {
-            return Ok(TraitArgs::External);
-        }
+        let mut tag: Option<LitStr> = None;
+        let mut content: Option<LitStr> = None;
+        let mut default_variant: Option<LitStr> = None;
+        let mut deny_unknown_fields: Option<kw::deny_unknown_fields> = None;
+        let mut registry: Option<Registry> = None;
+        let mut instantiate: Option<Vec<Path>> = None;
+        let mut unknown: Option<Type> = None;
+        let mut rename_all: Option<RenameRule> = None;
+        let mut namespace: Option<Namespace> = None;
+        let mut untagged: Option<kw::untagged> = None;
+        let mut repr: Option<LitStr> = None;
+        let mut no_suggestions: Option<kw::no_suggestions> = None;
+        let mut unit_shorthand: Option<kw::unit_shorthand> = None;
+        let mut limits: Option<Limits> = None;
+        let mut crate_path: Option<Path> = None;
 
-        input.parse::<kw::tag>()?;
-        input.parse::<Token![=]>()?;
-        let tag: LitStr = input.parse()?;
-        if !input.is_empty() {
+        while !input.is_empty() {
+            let lookahead = input.lookahead1();
+            if tag.is_none() && lookahead.peek(kw::tag) {
+                input.parse::<kw::tag>()?;
+                input.parse::<Token![=]>()?;
+                tag = Some(input.parse()?);
+            } else if content.is_none() && lookahead.peek(kw::content) {
+                input.parse::<kw::content>()?;
+                input.parse::<Token![=]>()?;
+                content = Some(input.parse()?);
+            } else if default_variant.is_none() && lookahead.peek(kw::default_variant) {
+                input.parse::<kw::default_variant>()?;
+                input.parse::<Token![=]>()?;
+                default_variant = Some(input.parse()?);
+            } else if deny_unknown_fields.is_none() && lookahead.peek(kw::deny_unknown_fields) {
+                deny_unknown_fields = Some(input.parse()?);
+            } else if registry.is_none() && lookahead.peek(kw::registry) {
+                registry = Some(input.parse()?);
+            } else if instantiate.is_none() && lookahead.peek(kw::instantiate) {
+                input.parse::<kw::instantiate>()?;
+                let content;
+                parenthesized!(content in input);
+                let paths = content.parse_terminated(Path::parse, Token![,])?;
+                instantiate = Some(paths.into_iter().collect());
+            } else if unknown.is_none() && lookahead.peek(kw::unknown) {
+                input.parse::<kw::unknown>()?;
+                input.parse::<Token![=]>()?;
+                unknown = Some(input.parse()?);
+            } else if rename_all.is_none() && lookahead.peek(kw::rename_all) {
+                input.parse::<kw::rename_all>()?;
+                input.parse::<Token![=]>()?;
+                rename_all = Some(RenameRule::from_lit(&input.parse()?)?);
+            } else if namespace.is_none() && lookahead.peek(kw::namespace) {
+                input.parse::<kw::namespace>()?;
+                input.parse::<Token![=]>()?;
+                let lit: LitStr = input.parse()?;
+                namespace = Some(match lit.value().as_str() {
+                    "crate" => Namespace::Crate,
+                    "module" => Namespace::Module,
+                    _ => {
+                        let msg = "expected \"crate\" or \"module\"";
+                        return Err(Error::new(lit.span(), msg));
+                    }
+                });
+            } else if untagged.is_none() && lookahead.peek(kw::untagged) {
+                untagged = Some(input.parse()?);
+            } else if repr.is_none() && lookahead.peek(kw::repr) {
+                input.parse::<kw::repr>()?;
+                input.parse::<Token![=]>()?;
+                let lit: LitStr = input.parse()?;
+                if lit.value() != "enum" {
+                    let msg = "expected \"enum\"";
+                    return Err(Error::new(lit.span(), msg));
+                }
+                repr = Some(lit);
+            } else if no_suggestions.is_none() && lookahead.peek(kw::no_suggestions) {
+                no_suggestions = Some(input.parse()?);
+            } else if unit_shorthand.is_none() && lookahead.peek(kw::unit_shorthand) {
+                unit_shorthand = Some(input.parse()?);
+            } else if limits.is_none() && lookahead.peek(kw::limits) {
+                input.parse::<kw::limits>()?;
+                let content;
+                parenthesized!(content in input);
+                limits = Some(content.parse()?);
+            } else if crate_path.is_none() && lookahead.peek(Token![crate]) {
+                crate_path = Some(parse_crate_path(input)?);
+            } else {
+                return Err(lookahead.error());
+            }
+            if input.is_empty() {
+                break;
+            }
             input.parse::<Token![,]>()?;
         }
-        if input.is_empty() {
-            return Ok(TraitArgs::Internal {
-                tag,
-                default_variant: None,
-            });
+
+        if let (Some(repr), Some(_)) = (&repr, untagged) {
+            let msg = "repr = \"enum\" can not be combined with untagged";
+            return Err(Error::new(repr.span(), msg));
         }
 
-        let lookahead = input.lookahead1();
-        if lookahead.peek(kw::content) {
-            input.parse::<kw::content>()?;
-            input.parse::<Token![=]>()?;
-            let content: LitStr = input.parse()?;
-            if !input.is_empty() {
-                input.parse::<Token![,]>()?;
-            }
-
-            let mut default_variant = None;
-            let mut deny_unknown_fields = false;
-            while !input.is_empty() {
-                let lookahead = input.lookahead1();
-                if default_variant.is_none() && lookahead.peek(kw::default_variant) {
-                    input.parse::<kw::default_variant>()?;
-                    input.parse::<Token![=]>()?;
-                    default_variant = Some(input.parse()?);
-                } else if !deny_unknown_fields && lookahead.peek(kw::deny_unknown_fields) {
-                    input.parse::<kw::deny_unknown_fields>()?;
-                    deny_unknown_fields = true;
-                } else {
-                    return Err(lookahead.error());
+        if let (Some(_), Some(unknown)) = (&repr, &unknown) {
+            let msg = "unknown tags can not be serialized as enum variants";
+            return Err(Error::new_spanned(unknown, msg));
+        }
+
+        if let Some(unit_shorthand) = unit_shorthand {
+            if untagged.is_some() || repr.is_some() {
+                let msg = "unit_shorthand requires an externally, internally or adjacently tagged trait object";
+                return Err(Error::new(unit_shorthand.span, msg));
+            }
+        }
+
+        let tagging = match (tag, content) {
+            (None, None) if repr.is_some() => {
+                if let Some(default_variant) = default_variant {
+                    let msg = "default_variant requires a tag";
+                    return Err(Error::new(default_variant.span(), msg));
                 }
-                if !input.is_empty() {
-                    input.parse::<Token![,]>()?;
+                if let Some(deny_unknown_fields) = deny_unknown_fields {
+                    let msg = "deny_unknown_fields requires a tag and content";
+                    return Err(Error::new(deny_unknown_fields.span, msg));
                 }
+                Tagging::Enum
             }
-
-            Ok(TraitArgs::Adjacent {
+            (Some(tag), _) if repr.is_some() => {
+                let msg = "enum trait objects take their tag from the variant name";
+                return Err(Error::new(tag.span(), msg));
+            }
+            (None, None) if untagged.is_some() => {
+                if let Some(default_variant) = default_variant {
+                    let msg = "default_variant requires a tag";
+                    return Err(Error::new(default_variant.span(), msg));
+                }
+                if let Some(deny_unknown_fields) = deny_unknown_fields {
+                    let msg = "deny_unknown_fields requires a tag and content";
+                    return Err(Error::new(deny_unknown_fields.span, msg));
+                }
+                if let Some(unknown) = &unknown {
+                    let msg = "untagged trait objects have no tag to fall back on";
+                    return Err(Error::new_spanned(unknown, msg));
+                }
+                Tagging::Untagged
+            }
+            (Some(tag), _) if untagged.is_some() => {
+                let msg = "untagged trait objects can not have a tag";
+                return Err(Error::new(tag.span(), msg));
+            }
+            (None, None) => {
+                if let Some(default_variant) = default_variant {
+                    let msg = "default_variant requires a tag";
+                    return Err(Error::new(default_variant.span(), msg));
+                }
+                if let Some(deny_unknown_fields) = deny_unknown_fields {
+                    let msg = "deny_unknown_fields requires a tag and content";
+                    return Err(Error::new(deny_unknown_fields.span, msg));
+                }
+                Tagging::External
+            }
+            (Some(tag), None) => {
+                if let Some(deny_unknown_fields) = deny_unknown_fields {
+                    let msg = "deny_unknown_fields requires a tag and content";
+                    return Err(Error::new(deny_unknown_fields.span, msg));
+                }
+                Tagging::Internal {
+                    tag,
+                    default_variant,
+                }
+            }
+            (Some(tag), Some(content)) => Tagging::Adjacent {
                 tag,
                 content,
                 default_variant,
-                deny_unknown_fields,
-            })
-        } else if lookahead.peek(kw::default_variant) {
-            input.parse::<kw::default_variant>()?;
-            input.parse::<Token![=]>()?;
-            let default_variant: LitStr = input.parse()?;
-            input.parse::<Option<Token![,]>>()?;
-            Ok(TraitArgs::Internal {
-                tag,
-                default_variant: Some(default_variant),
-            })
-        } else {
-            Err(lookahead.error())
-        }
+                deny_unknown_fields: deny_unknown_fields.is_some(),
+            },
+            (None, Some(content)) => {
+                let msg = "content requires a tag";
+                return Err(Error::new(content.span(), msg));
+            }
+        };
+
+        Ok(TraitArgs {
+            tagging,
+            registry: registry.unwrap_or(Registry::Inventory),
+            instantiate: instantiate.unwrap_or_default(),
+            unknown,
+            rename_all,
+            namespace,
+            suggestions: no_suggestions.is_none(),
+            unit_shorthand: unit_shorthand.is_some(),
+            limits: limits.unwrap_or_default(),
+            crate_path: crate_path.unwrap_or_else(|| parse_quote!(typetag)),
+        })
     }
 }
 
 // #[typetag::serde]
 // #[typetag::serde(name = "Tag")]
 // #[typetag::serde(name = CONSTANT)]
+// #[typetag::serde(name = "Tag", alias = "OldTag", alias = "old_tag")]
+// #[typetag::serde(name = "Tag", id = 7)]
+// #[typetag::serde(priority = 1)]
+// #[typetag::serde(instances(Wrapper<A> = "WrapperA", Wrapper<B> = "WrapperB"))]
+// #[typetag::serde(unknown)]
+// #[typetag::serde(context = Context)]
+// #[typetag::serde(registry = "manual")]
+// #[typetag::serde(crate = "facade::typetag")]
 impl Parse for ImplArgs {
     fn parse(input: ParseStream) -> Result<Self> {
-        let name = if input.is_empty() {
-            None
+        let mut name: Option<Expr> = None;
+        let mut aliases: Vec<Expr> = Vec::new();
+        let mut id: Option<Expr> = None;
+        let mut priority: Option<Expr> = None;
+        let mut instances: Option<Vec<Instance>> = None;
+        let mut unknown: Option<kw::unknown> = None;
+        let mut context: Option<Type> = None;
+        let mut registry: Option<Registry> = None;
+        let mut crate_path: Option<Path> = None;
+
+        while !input.is_empty() {
+            let lookahead = input.lookahead1();
+            if name.is_none()
+                && instances.is_none()
+                && unknown.is_none()
+                && lookahead.peek(kw::name)
+            {
+                input.parse::<kw::name>()?;
+                input.parse::<Token![=]>()?;
+                name = Some(input.parse()?);
+            } else if instances.is_none() && unknown.is_none() && lookahead.peek(kw::alias) {
+                input.parse::<kw::alias>()?;
+                input.parse::<Token![=]>()?;
+                aliases.push(input.parse()?);
+            } else if id.is_none()
+                && instances.is_none()
+                && unknown.is_none()
+                && lookahead.peek(kw::id)
+            {
+                input.parse::<kw::id>()?;
+                input.parse::<Token![=]>()?;
+                id = Some(input.parse()?);
+            } else if priority.is_none()
+                && instances.is_none()
+                && unknown.is_none()
+                && lookahead.peek(kw::priority)
+            {
+                input.parse::<kw::priority>()?;
+                input.parse::<Token![=]>()?;
+                priority = Some(input.parse()?);
+            } else if name.is_none()
+                && aliases.is_empty()
+                && id.is_none()
+                && priority.is_none()
+                && instances.is_none()
+                && unknown.is_none()
+                && lookahead.peek(kw::instances)
+            {
+                input.parse::<kw::instances>()?;
+                let content;
+                parenthesized!(content in input);
+                let list = content.parse_terminated(Instance::parse, Token![,])?;
+                instances = Some(list.into_iter().collect());
+            } else if name.is_none()
+                && aliases.is_empty()
+                && id.is_none()
+                && priority.is_none()
+                && instances.is_none()
+                && unknown.is_none()
+                && context.is_none()
+                && lookahead.peek(kw::unknown)
+            {
+                unknown = Some(input.parse()?);
+            } else if context.is_none() && unknown.is_none() && lookahead.peek(kw::context) {
+                input.parse::<kw::context>()?;
+                input.parse::<Token![=]>()?;
+                context = Some(input.parse()?);
+            } else if registry.is_none() && lookahead.peek(kw::registry) {
+                registry = Some(input.parse()?);
+            } else if crate_path.is_none() && lookahead.peek(Token![crate]) {
+                crate_path = Some(parse_crate_path(input)?);
+            } else {
+                return Err(lookahead.error());
+            }
+            if input.is_empty() {
+                break;
+            }
+            input.parse::<Token![,]>()?;
+        }
+
+        Ok(ImplArgs {
+            name,
+            aliases,
+            id,
+            priority,
+            instances: instances.unwrap_or_default(),
+            unknown: unknown.is_some(),
+            context,
+            registry: registry.unwrap_or(Registry::Inventory),
+            crate_path: crate_path.unwrap_or_else(|| parse_quote!(typetag)),
+        })
+    }
+}
+
+// Wrapper<A> = "WrapperA"
+// Wrapper<A> = ("WrapperA", id = 7)
+impl Parse for Instance {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let ty: Type = input.parse()?;
+        input.parse::<Token![=]>()?;
+        if input.peek(token::Paren) {
+            let content;
+            parenthesized!(content in input);
+            let name: Expr = content.parse()?;
+            content.parse::<Token![,]>()?;
+            content.parse::<kw::id>()?;
+            content.parse::<Token![=]>()?;
+            let id: Expr = content.parse()?;
+            content.parse::<Option<Token![,]>>()?;
+            if !content.is_empty() {
+                return Err(content.error("unexpected token"));
+            }
+            Ok(Instance {
+                ty,
+                name,
+                id: Some(id),
+            })
+        } else {
+            let name: Expr = input.parse()?;
+            Ok(Instance { ty, name, id: None })
+        }
+    }
+}
+
+// typetag::register!(dyn Trait => [A, B, C])
+// typetag::register!(crate = "facade::typetag", dyn Trait => [A, B, C])
+impl Parse for RegisterInput {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let crate_path = if input.peek(Token![crate]) {
+            let crate_path = parse_crate_path(input)?;
+            input.parse::<Token![,]>()?;
+            crate_path
         } else {
-            input.parse::<kw::name>()?;
-            input.parse::<Token![=]>()?;
-            let expr: Expr = input.parse()?;
-            input.parse::<Option<Token![,]>>()?;
-            Some(expr)
+            parse_quote!(typetag)
         };
-        Ok(ImplArgs { name })
+        let object: Type = input.parse()?;
+        input.parse::<Token![=>]>()?;
+        let content;
+        bracketed!(content in input);
+        let types = content.parse_terminated(Type::parse, Token![,])?;
+        input.parse::<Option<Token![,]>>()?;
+        Ok(RegisterInput {
+            crate_path,
+            object,
+            types,
+        })
     }
 }
 
-fn is_self_sized(generics: &Generics) -> bool {
+pub fn is_self_sized(generics: &Generics) -> bool {
     if let Some(where_clause) = &generics.where_clause {
         for predicate in &where_clause.predicates {
             if let WherePredicate::Type(pred_type) = predicate {
@@ -205,3 +574,63 @@ fn is_self_sized(generics: &Generics) -> bool {
     }
     false
 }
+
+// Trait<T> + [(Assoc, X)] -> Trait<T, Assoc = X>
+pub fn with_assoc_types<I>(path: &Path, assoc_types: I) -> Path
+where
+    I: IntoIterator<Item = (Ident, TokenStream)>,
+{
+    let mut path = path.clone();
+    let segment = path.segments.last_mut().unwrap();
+    for (ident, ty) in assoc_types {
+        if let PathArguments::None = segment.arguments {
+            segment.arguments = PathArguments::AngleBracketed(parse_quote!(<>));
+        }
+        if let PathArguments::AngleBracketed(arguments) = &mut segment.arguments {
+            arguments.args.push(parse_quote!(#ident = #ty));
+        }
+    }
+    path
+}
+
+// limits(bytes = 65536, elements = 1024, depth = 16, recursion = 64)
+impl Parse for Limits {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let mut limits = Limits::default();
+        while !input.is_empty() {
+            let lookahead = input.lookahead1();
+            if limits.bytes.is_none() && lookahead.peek(kw::bytes) {
+                input.parse::<kw::bytes>()?;
+                input.parse::<Token![=]>()?;
+                limits.bytes = Some(input.parse()?);
+            } else if limits.elements.is_none() && lookahead.peek(kw::elements) {
+                input.parse::<kw::elements>()?;
+                input.parse::<Token![=]>()?;
+                limits.elements = Some(input.parse()?);
+            } else if limits.depth.is_none() && lookahead.peek(kw::depth) {
+                input.parse::<kw::depth>()?;
+                input.parse::<Token![=]>()?;
+                limits.depth = Some(input.parse()?);
+            } else if limits.recursion.is_none() && lookahead.peek(kw::recursion) {
+                input.parse::<kw::recursion>()?;
+                input.parse::<Token![=]>()?;
+                limits.recursion = Some(input.parse()?);
+            } else {
+                return Err(lookahead.error());
+            }
+            if input.is_empty() {
+                break;
+            }
+            input.parse::<Token![,]>()?;
+        }
+        Ok(limits)
+    }
+}
+
+// crate = "facade::typetag"
+fn parse_crate_path(input: ParseStream) -> Result<Path> {
+    input.parse::<Token![crate]>()?;
+    input.parse::<Token![=]>()?;
+    let lit: LitStr = input.parse()?;
+    lit.parse()
+}
@@ -0,0 +1,23 @@
+use crate::parse::RegisterInput;
+use crate::private;
+use proc_macro2::TokenStream;
+use quote::quote;
+
+pub(crate) fn expand(input: RegisterInput) -> TokenStream {
+    let typetag = &input.crate_path;
+    let object = &input.object;
+
+    let registrations = input.types.iter().map(|ty| {
+        quote! {
+            <#ty as #typetag::#private::TaggedDeserialize<#object>>::REGISTRATION
+        }
+    });
+
+    quote! {
+        impl #typetag::#private::ManualRegistry for #object {
+            const REGISTRATIONS: &'static [#typetag::#private::Registration<
+                <Self as #typetag::#private::Strictest>::Object,
+            >] = &[#(#registrations),*];
+        }
+    }
+}
@@ -1,61 +1,314 @@
+use crate::case::RenameRule;
+use crate::parse::{with_assoc_types, Instance, Registry};
 use crate::{private, ImplArgs, Mode};
-use proc_macro2::TokenStream;
-use quote::quote;
-use syn::{parse_quote, Error, ItemImpl, Type, TypePath};
+use proc_macro2::{Ident, TokenStream, TokenTree};
+use quote::{quote, ToTokens};
+use syn::{
+    parse_quote, Error, GenericArgument, GenericParam, ImplItem, ItemImpl, Path, PathArguments,
+    Type, TypePath,
+};
 
 pub(crate) fn expand(args: ImplArgs, mut input: ItemImpl, mode: Mode) -> TokenStream {
-    if mode.de && !input.generics.params.is_empty() {
-        let msg = "deserialization of generic impls is not supported yet; \
-                   use #[typetag::serialize] to generate serialization only";
-        return Error::new_spanned(input.generics, msg).to_compile_error();
-    }
+    let typetag = &args.crate_path;
+    let (object, _for_token) = input.trait_.as_ref().unwrap();
+    let assoc_types = input.items.iter().filter_map(|item| match item {
+        ImplItem::Type(assoc) if assoc.generics.params.is_empty() => {
+            Some((assoc.ident.clone(), assoc.ty.to_token_stream()))
+        }
+        _ => None,
+    });
+    let object = with_assoc_types(object, assoc_types.collect::<Vec<_>>());
+    let no_id = quote!(#typetag::#private::Option::None);
+    let tagged = quote!(<Self as #typetag::#private::Tagged<dyn #object>>);
 
-    let name = match args.name {
-        Some(name) => quote!(#name),
-        None => match type_name(&input.self_ty) {
+    let registrations = if args.unknown {
+        // The fallback for unrecognized tags reports whichever tag it was
+        // deserialized from, and is not registered under any name itself.
+        let name = quote! {
+            #typetag::Unknown::tag(
+                <Self as #typetag::#private::AsRef<#typetag::Unknown>>::as_ref(self),
+            )
+        };
+        let static_name = quote!(#typetag::#private::Option::None);
+        let id = quote!(#typetag::#private::Id::None);
+        augment_impl(&mut input, typetag, &name, &static_name, &id, mode);
+        return quote!(#input);
+    } else if args.instances.is_empty() {
+        if mode.de && !input.generics.params.is_empty() {
+            let msg = "deserialization of generic impls requires a list of instances, \
+                       as in #[typetag::serde(instances(...))]; \
+                       use #[typetag::serialize] to generate serialization only";
+            return Error::new_spanned(input.generics, msg).to_compile_error();
+        }
+
+        let explicit_name = args.name.is_some();
+        let name = match args.name {
             Some(name) => quote!(#name),
-            None => {
-                let msg = "use #[typetag::serde(name = \"...\")] to specify a unique name";
-                return Error::new_spanned(&input.self_ty, msg).to_compile_error();
-            }
-        },
-    };
+            None => match type_name(&input.self_ty) {
+                Some(name) => {
+                    let names: Vec<String> = RenameRule::ALL
+                        .iter()
+                        .map(|rule| rule.apply(&name))
+                        .collect();
+                    let crate_qualified = (0..names.len()).map(
+                        |i| quote!(#typetag::#private::CrateQualified::get(&CRATE_QUALIFIED, #i)),
+                    );
+                    let module_qualified = names.iter().map(|name| {
+                        quote!(#typetag::#private::concat!(#typetag::#private::module_path!(), "::", #name))
+                    });
+                    quote!({
+                        const CRATE_QUALIFIED: #typetag::#private::CrateQualified<
+                            { #typetag::#private::crate_qualified_len(#typetag::#private::module_path!(), [#(#names),*]) },
+                        > = #typetag::#private::CrateQualified::new(#typetag::#private::module_path!(), [#(#names),*]);
+                        <dyn #object>::typetag_rename([
+                            [#(#names),*],
+                            [#(#crate_qualified),*],
+                            [#(#module_qualified),*],
+                        ])
+                    })
+                }
+                None => {
+                    let msg = "use #[typetag::serde(name = \"...\")] to specify a unique name";
+                    return Error::new_spanned(&input.self_ty, msg).to_compile_error();
+                }
+            },
+        };
 
-    augment_impl(&mut input, &name, mode);
+        let id = match args.id {
+            Some(id) => quote!(#typetag::#private::Option::Some(#id)),
+            None => no_id,
+        };
+        let priority = match args.priority {
+            Some(priority) => quote!(#priority),
+            None => quote!(0),
+        };
 
-    let (object, _for_token) = &input.trait_.as_ref().unwrap();
-    let this = &input.self_ty;
+        // Default names are picked out of every candidate at compile time, in
+        // the TAG constant of registered impls, rather than on each call to
+        // typetag_name. Serialize-only impls are not registered and have no
+        // Tagged impl, which a generic impl like `impl<T> Trait for Box<T>`
+        // could not provide without breaking the orphan rule; non-generic ones
+        // get a constant of their own instead.
+        let tag = if mode.de {
+            quote!(#tagged::TAG)
+        } else if explicit_name || !input.generics.params.is_empty() {
+            name.clone()
+        } else {
+            quote!({
+                const TAG: &'static str = #name;
+                TAG
+            })
+        };
+        let static_name = quote!(#typetag::#private::Option::Some(#tag));
+        // Serialize-only impls are not registered, so there are no other ids
+        // to be consistent with.
+        let registry_has_ids = if mode.de {
+            quote!(#tagged::registry_has_ids)
+        } else {
+            quote!(|| false)
+        };
+        let impl_id = quote!(#typetag::#private::Id::new(#id, #registry_has_ids));
+        augment_impl(&mut input, typetag, &tag, &static_name, &impl_id, mode);
 
-    let mut expanded = quote! {
-        #input
+        if !mode.de {
+            return quote!(#input);
+        }
+        let aliases = args.aliases;
+        vec![(
+            (*input.self_ty).clone(),
+            object,
+            name,
+            quote!(&[#(#aliases),*]),
+            id,
+            priority,
+        )]
+    } else {
+        if input.generics.params.is_empty() {
+            let msg = "instances are only supported on generic impls";
+            return Error::new_spanned(&input.self_ty, msg).to_compile_error();
+        }
+
+        let mut registrations = Vec::new();
+        for instance in args.instances {
+            match instantiate(&input, &object, &instance) {
+                Ok(instance_object) => {
+                    let name = &instance.name;
+                    let id = match &instance.id {
+                        Some(id) => quote!(#typetag::#private::Option::Some(#id)),
+                        None => no_id.clone(),
+                    };
+                    registrations.push((
+                        instance.ty,
+                        instance_object,
+                        quote!(#name),
+                        quote!(&[]),
+                        id,
+                        quote!(0),
+                    ));
+                }
+                Err(err) => return err.to_compile_error(),
+            }
+        }
+
+        let name = quote!(#tagged::TAG);
+        let static_name = quote!(#typetag::#private::Option::Some(#name));
+        let id = quote!(#typetag::#private::Id::new(#tagged::ID, #tagged::registry_has_ids));
+        augment_impl(&mut input, typetag, &name, &static_name, &id, mode);
+        input
+            .generics
+            .make_where_clause()
+            .predicates
+            .push(parse_quote!(Self: #typetag::#private::Tagged<dyn #object>));
+        input
+            .attrs
+            .push(parse_quote!(#[allow(unknown_lints, unused_associated_type_bounds)]));
+        registrations
     };
 
-    if mode.de {
+    let mut expanded = TokenStream::new();
+    let context = args.context;
+    let registry = args.registry;
+
+    for (this, object, name, aliases, id, priority) in registrations {
+        let registry_has_ids = if mode.de {
+            quote! {
+                fn registry_has_ids() -> bool {
+                    !<dyn #object as #typetag::#private::Registered>::registry().ids.is_empty()
+                }
+            }
+        } else {
+            TokenStream::new()
+        };
         expanded.extend(quote! {
-            typetag::#private::inventory::submit! {
-                <dyn #object>::typetag_register(
-                    #name,
-                    (|deserializer| typetag::#private::Result::Ok(
-                        typetag::#private::Box::new(
-                            typetag::#private::erased_serde::deserialize::<#this>(deserializer)?
-                        ),
-                    )) as typetag::#private::DeserializeFn<<dyn #object as typetag::#private::Strictest>::Object>,
-                )
+            impl #typetag::#private::Tagged<dyn #object> for #this {
+                const TAG: &'static str = #name;
+                const ALIASES: &'static [&'static str] = #aliases;
+                const ID: #typetag::#private::Option<u32> = #id;
+                const PRIORITY: i32 = #priority;
+
+                #registry_has_ids
             }
         });
+
+        if mode.de {
+            let tag = quote!(<Self as #typetag::#private::Tagged<dyn #object>>::TAG);
+            let (deserialize, seeded) = match &context {
+                None => (
+                    quote! {
+                        |deserializer| {
+                            #typetag::#private::Result::Ok(#typetag::#private::Box::new(
+                                #typetag::#private::erased_serde::deserialize::<#this>(deserializer)?,
+                            ))
+                        }
+                    },
+                    quote!(#typetag::#private::Option::None),
+                ),
+                Some(context) => (
+                    quote! {
+                        |_deserializer| {
+                            #typetag::#private::missing_context::<_, #context>()
+                        }
+                    },
+                    quote! {
+                        #typetag::#private::Option::Some(|context, deserializer| {
+                            let context = #typetag::#private::downcast_context::<#context>(context)?;
+                            #typetag::#private::Result::Ok(#typetag::#private::Box::new(
+                                <#this as #typetag::#private::DeserializeWithContext<#context>>::deserialize_with_context(
+                                    context,
+                                    deserializer,
+                                )?,
+                            ))
+                        })
+                    },
+                ),
+            };
+
+            expanded.extend(quote! {
+                impl #typetag::#private::TaggedDeserialize<dyn #object> for #this {
+                    const REGISTRATION: #typetag::#private::Registration<
+                        <dyn #object as #typetag::#private::Strictest>::Object,
+                    > = #typetag::#private::Registration {
+                        name: #tag,
+                        aliases: <Self as #typetag::#private::Tagged<dyn #object>>::ALIASES,
+                        id: <Self as #typetag::#private::Tagged<dyn #object>>::ID,
+                        priority: <Self as #typetag::#private::Tagged<dyn #object>>::PRIORITY,
+                        deserialize: #deserialize,
+                        seeded: #seeded,
+                        origin: #typetag::Origin::new(
+                            #typetag::#private::type_name::<Self>,
+                            #typetag::#private::module_path!(),
+                            #typetag::#private::file!(),
+                            #typetag::#private::line!(),
+                        ),
+                    };
+                }
+
+            });
+
+            let assert_registry = match registry {
+                Registry::Inventory => quote!(assert_registry_inventory),
+                Registry::Manual => quote!(assert_registry_manual),
+            };
+            expanded.extend(quote! {
+                const _: fn() = #typetag::#private::#assert_registry::<dyn #object>;
+            });
+
+            if let Registry::Inventory = registry {
+                expanded.extend(quote! {
+                    #typetag::#private::inventory::submit! {
+                        <dyn #object>::typetag_register(
+                            <#this as #typetag::#private::TaggedDeserialize<dyn #object>>::REGISTRATION,
+                        )
+                    }
+                });
+            }
+        }
     }
 
-    expanded
+    quote! {
+        #input
+
+        // The trait object type names every associated type of the impl, even
+        // ones that only exist on Sized implementors.
+        #[allow(unknown_lints, unused_associated_type_bounds)]
+        const _: () = {
+            #expanded
+        };
+    }
 }
 
-fn augment_impl(input: &mut ItemImpl, name: &TokenStream, mode: Mode) {
+fn augment_impl(
+    input: &mut ItemImpl,
+    typetag: &Path,
+    name: &TokenStream,
+    static_name: &TokenStream,
+    id: &TokenStream,
+    mode: Mode,
+) {
     if mode.ser {
         input.items.push(parse_quote! {
             #[doc(hidden)]
-            fn typetag_name(&self) -> &'static str {
+            #[allow(unknown_lints, unused_associated_type_bounds)]
+            fn typetag_name(&self) -> &str {
                 #name
             }
         });
+        // Enum variant names must be 'static, which the unknown fallback's
+        // tag is not.
+        input.items.push(parse_quote! {
+            #[doc(hidden)]
+            #[allow(unknown_lints, unused_associated_type_bounds)]
+            fn typetag_static_name(&self) -> #typetag::#private::Option<&'static str> {
+                #static_name
+            }
+        });
+        input.items.push(parse_quote! {
+            #[doc(hidden)]
+            #[allow(unknown_lints, unused_associated_type_bounds)]
+            fn typetag_id(&self) -> #typetag::#private::Id {
+                #id
+            }
+        });
     }
 
     if mode.de {
@@ -83,3 +336,175 @@ fn type_name(mut ty: &Type) -> Option<String> {
         }
     }
 }
+
+// Match a concrete instance like `Wrapper<u8>` against the impl's self type
+// `Wrapper<T>`, and substitute the resulting `T = u8` into the trait path.
+fn instantiate(input: &ItemImpl, object: &Path, instance: &Instance) -> Result<Path, Error> {
+    let params: Vec<&Ident> = input
+        .generics
+        .params
+        .iter()
+        .filter_map(|param| match param {
+            GenericParam::Type(param) => Some(&param.ident),
+            GenericParam::Const(param) => Some(&param.ident),
+            GenericParam::Lifetime(_) => None,
+        })
+        .collect();
+
+    let mut bindings = Vec::new();
+    if !unify(&input.self_ty, &instance.ty, &params, &mut bindings) {
+        let self_ty = &input.self_ty;
+        let msg = format!(
+            "expected an instance of `{}`",
+            self_ty.to_token_stream().to_string().replace(' ', ""),
+        );
+        return Err(Error::new_spanned(&instance.ty, msg));
+    }
+
+    for param in params {
+        if !bindings.iter().any(|(ident, _)| ident == param) {
+            let msg = format!("cannot determine `{}` from this instance", param);
+            return Err(Error::new_spanned(&instance.ty, msg));
+        }
+    }
+
+    let tokens = substitute(object.to_token_stream(), &bindings);
+    syn::parse2(tokens)
+}
+
+fn unify(
+    pattern: &Type,
+    ty: &Type,
+    params: &[&Ident],
+    bindings: &mut Vec<(Ident, TokenStream)>,
+) -> bool {
+    match (pattern, ty) {
+        (Type::Group(pattern), _) => unify(&pattern.elem, ty, params, bindings),
+        (_, Type::Group(ty)) => unify(pattern, &ty.elem, params, bindings),
+        (Type::Paren(pattern), _) => unify(&pattern.elem, ty, params, bindings),
+        (_, Type::Paren(ty)) => unify(pattern, &ty.elem, params, bindings),
+        (Type::Path(pattern), _) if pattern.qself.is_none() && is_param(&pattern.path, params) => {
+            bind(
+                &pattern.path.segments[0].ident,
+                ty.to_token_stream(),
+                bindings,
+            )
+        }
+        (Type::Path(pattern), Type::Path(ty)) if pattern.qself.is_none() && ty.qself.is_none() => {
+            pattern.path.leading_colon.is_some() == ty.path.leading_colon.is_some()
+                && pattern.path.segments.len() == ty.path.segments.len()
+                && pattern
+                    .path
+                    .segments
+                    .iter()
+                    .zip(&ty.path.segments)
+                    .all(|(pattern, ty)| {
+                        pattern.ident == ty.ident
+                            && unify_arguments(&pattern.arguments, &ty.arguments, params, bindings)
+                    })
+        }
+        (Type::Reference(pattern), Type::Reference(ty)) => {
+            pattern.mutability.is_some() == ty.mutability.is_some()
+                && unify(&pattern.elem, &ty.elem, params, bindings)
+        }
+        (Type::Slice(pattern), Type::Slice(ty)) => unify(&pattern.elem, &ty.elem, params, bindings),
+        (Type::Array(pattern), Type::Array(ty)) => {
+            unify(&pattern.elem, &ty.elem, params, bindings) && {
+                let len = &pattern.len;
+                match syn::parse2::<Path>(quote!(#len)) {
+                    Ok(path) if is_param(&path, params) => {
+                        bind(&path.segments[0].ident, ty.len.to_token_stream(), bindings)
+                    }
+                    _ => same_tokens(&pattern.len, &ty.len),
+                }
+            }
+        }
+        (Type::Tuple(pattern), Type::Tuple(ty)) => {
+            pattern.elems.len() == ty.elems.len()
+                && pattern
+                    .elems
+                    .iter()
+                    .zip(&ty.elems)
+                    .all(|(pattern, ty)| unify(pattern, ty, params, bindings))
+        }
+        _ => same_tokens(pattern, ty),
+    }
+}
+
+fn unify_arguments(
+    pattern: &PathArguments,
+    ty: &PathArguments,
+    params: &[&Ident],
+    bindings: &mut Vec<(Ident, TokenStream)>,
+) -> bool {
+    match (pattern, ty) {
+        (PathArguments::None, PathArguments::None) => true,
+        (PathArguments::AngleBracketed(pattern), PathArguments::AngleBracketed(ty)) => {
+            pattern.args.len() == ty.args.len()
+                && pattern
+                    .args
+                    .iter()
+                    .zip(&ty.args)
+                    .all(|(pattern, ty)| match (pattern, ty) {
+                        (GenericArgument::Lifetime(_), GenericArgument::Lifetime(_)) => true,
+                        (GenericArgument::Type(pattern), GenericArgument::Type(ty)) => {
+                            unify(pattern, ty, params, bindings)
+                        }
+                        (
+                            GenericArgument::Type(Type::Path(pattern)),
+                            GenericArgument::Const(ty),
+                        ) if pattern.qself.is_none() && is_param(&pattern.path, params) => bind(
+                            &pattern.path.segments[0].ident,
+                            ty.to_token_stream(),
+                            bindings,
+                        ),
+                        _ => same_tokens(pattern, ty),
+                    })
+        }
+        _ => same_tokens(pattern, ty),
+    }
+}
+
+fn is_param(path: &Path, params: &[&Ident]) -> bool {
+    path.get_ident()
+        .is_some_and(|ident| params.contains(&ident))
+}
+
+fn bind(param: &Ident, value: TokenStream, bindings: &mut Vec<(Ident, TokenStream)>) -> bool {
+    match bindings.iter().find(|(ident, _)| ident == param) {
+        Some((_, existing)) => existing.to_string() == value.to_string(),
+        None => {
+            bindings.push((param.clone(), value));
+            true
+        }
+    }
+}
+
+fn same_tokens(a: &dyn ToTokens, b: &dyn ToTokens) -> bool {
+    a.to_token_stream().to_string() == b.to_token_stream().to_string()
+}
+
+fn substitute(tokens: TokenStream, bindings: &[(Ident, TokenStream)]) -> TokenStream {
+    let mut substituted = TokenStream::new();
+    let mut after_apostrophe = false;
+    for token in tokens {
+        let is_apostrophe = matches!(&token, TokenTree::Punct(punct) if punct.as_char() == '\'');
+        match token {
+            TokenTree::Group(group) => {
+                let stream = substitute(group.stream(), bindings);
+                let mut replacement = proc_macro2::Group::new(group.delimiter(), stream);
+                replacement.set_span(group.span());
+                substituted.extend(Some(TokenTree::Group(replacement)));
+            }
+            TokenTree::Ident(ident) if !after_apostrophe => {
+                match bindings.iter().find(|(param, _)| *param == ident) {
+                    Some((_, value)) => substituted.extend(value.clone()),
+                    None => substituted.extend(Some(TokenTree::Ident(ident))),
+                }
+            }
+            token => substituted.extend(Some(token)),
+        }
+        after_apostrophe = is_apostrophe;
+    }
+    substituted
+}
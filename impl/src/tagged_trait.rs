@@ -1,29 +1,85 @@
+use crate::case::RenameRule;
+use crate::parse::{is_self_sized, with_assoc_types, Limits, Namespace, Registry, Tagging};
 use crate::{private, Mode, TraitArgs};
-use proc_macro2::TokenStream;
-use quote::quote;
-use syn::{parse_quote, Error, ItemTrait, LitStr, TypeParamBound};
+use proc_macro2::{Ident, TokenStream};
+use quote::{format_ident, quote, ToTokens};
+use std::iter;
+use syn::{
+    parse_quote, Error, Expr, GenericParam, Generics, ItemTrait, LitStr, Path, TraitItem,
+    TraitItemType, Type, TypeParamBound,
+};
 
 pub(crate) fn expand(args: TraitArgs, mut input: ItemTrait, mode: Mode) -> TokenStream {
-    if mode.de && !input.generics.params.is_empty() {
-        let msg = "deserialization of generic traits is not supported yet; \
-                   use #[typetag::serialize] to generate serialization only";
-        return Error::new_spanned(input.generics, msg).to_compile_error();
+    let typetag = &args.crate_path;
+
+    // Associated types that are part of the trait object type, as in
+    // `dyn Trait<Assoc = X>`, versus ones only usable on Sized implementors.
+    let mut object_assoc_types = Vec::new();
+    let mut sized_assoc_types = Vec::new();
+    for item in &input.items {
+        if let TraitItem::Type(assoc) = item {
+            if is_self_sized(&assoc.generics) {
+                sized_assoc_types.push(assoc.clone());
+            } else {
+                object_assoc_types.push(assoc.clone());
+            }
+        }
     }
 
-    augment_trait(&mut input, mode);
+    let instantiations = if args.instantiate.is_empty() {
+        if mode.de && !input.generics.params.is_empty() {
+            let msg = "deserialization of generic traits requires a list of instantiations, \
+                       as in #[typetag::serde(instantiate(...))]; \
+                       use #[typetag::serialize] to generate serialization only";
+            return Error::new_spanned(input.generics, msg).to_compile_error();
+        }
+        if let (true, Some(assoc)) = (mode.de, object_assoc_types.first()) {
+            let type_token = assoc.type_token;
+            let semi_token = assoc.semi_token;
+            let span = quote!(#type_token #semi_token);
+            let msg = format!(
+                "deserialization of traits with associated types requires a list of \
+                 instantiations, as in #[typetag::serde(instantiate({}<{} = ...>))]; \
+                 use #[typetag::serialize] to generate serialization only",
+                input.ident, assoc.ident,
+            );
+            return Error::new_spanned(span, msg).to_compile_error();
+        }
+        let object = &input.ident;
+        vec![parse_quote!(#object)]
+    } else {
+        for instantiation in &args.instantiate {
+            if instantiation.segments.last().unwrap().ident != input.ident {
+                let msg = format!("expected an instantiation of `{}`", input.ident);
+                return Error::new_spanned(instantiation, msg).to_compile_error();
+            }
+        }
+        args.instantiate
+    };
+
+    augment_trait(&mut input, typetag, mode);
 
-    let (serialize_impl, deserialize_impl) = match args {
-        TraitArgs::External => externally_tagged(&input),
-        TraitArgs::Internal {
+    let (serialize_impl, deserialize_impl) = match args.tagging {
+        Tagging::External => externally_tagged(&input, typetag),
+        Tagging::Internal {
             tag,
             default_variant,
-        } => internally_tagged(tag, default_variant, &input),
-        TraitArgs::Adjacent {
+        } => internally_tagged(tag, default_variant, &input, typetag),
+        Tagging::Adjacent {
             tag,
             content,
             default_variant,
             deny_unknown_fields,
-        } => adjacently_tagged(tag, content, default_variant, deny_unknown_fields, &input),
+        } => adjacently_tagged(
+            tag,
+            content,
+            default_variant,
+            deny_unknown_fields,
+            &input,
+            typetag,
+        ),
+        Tagging::Untagged => untagged(&input, typetag),
+        Tagging::Enum => enum_repr(&input, typetag),
     };
 
     let object = &input.ident;
@@ -31,41 +87,75 @@ pub(crate) fn expand(args: TraitArgs, mut input: ItemTrait, mode: Mode) -> Token
     let mut expanded = TokenStream::new();
 
     if mode.ser {
+        let (params, bindings) = assoc_type_params(&object_assoc_types);
         let mut impl_generics = input.generics.clone();
         impl_generics.params.push(parse_quote!('typetag));
+        impl_generics.params.extend(params);
         let (impl_generics, _, _) = impl_generics.split_for_impl();
         let (_, ty_generics, where_clause) = input.generics.split_for_impl();
+        let ser_object = with_assoc_types(&parse_quote!(#object #ty_generics), bindings);
 
         expanded.extend(quote! {
-            impl #impl_generics typetag::#private::serde::Serialize
-            for dyn #object #ty_generics + 'typetag #where_clause {
-                fn serialize<S>(&self, serializer: S) -> typetag::#private::Result<S::Ok, S::Error>
+            impl #impl_generics #typetag::#private::serde::Serialize
+            for dyn #ser_object + 'typetag #where_clause {
+                fn serialize<S>(&self, serializer: S) -> #typetag::#private::Result<S::Ok, S::Error>
                 where
-                    S: typetag::#private::serde::Serializer,
+                    S: #typetag::#private::serde::Serializer,
                 {
                     #serialize_impl
                 }
             }
         });
 
+        let object_name = object.to_string();
+        for marker_traits in &[
+            quote!(),
+            quote!(+ Send),
+            quote!(+ Sync),
+            quote!(+ Send + Sync),
+        ] {
+            expanded.extend(quote! {
+                impl #impl_generics #typetag::#private::Serializable
+                for dyn #ser_object #marker_traits + 'typetag #where_clause {
+                    const NAME: &'static str = #object_name;
+
+                    fn typetag_name(&self) -> &str {
+                        <Self as #object #ty_generics>::typetag_name(self)
+                    }
+
+                    fn typetag_id(&self) -> #typetag::#private::Id {
+                        <Self as #object #ty_generics>::typetag_id(self)
+                    }
+                }
+            });
+        }
+
         for marker_traits in &[quote!(Send), quote!(Sync), quote!(Send + Sync)] {
             expanded.extend(quote! {
-                impl #impl_generics typetag::#private::serde::Serialize
-                for dyn #object #ty_generics + #marker_traits + 'typetag #where_clause {
-                    fn serialize<S>(&self, serializer: S) -> typetag::#private::Result<S::Ok, S::Error>
+                impl #impl_generics #typetag::#private::serde::Serialize
+                for dyn #ser_object + #marker_traits + 'typetag #where_clause {
+                    fn serialize<S>(&self, serializer: S) -> #typetag::#private::Result<S::Ok, S::Error>
                     where
-                        S: typetag::#private::serde::Serializer,
+                        S: #typetag::#private::serde::Serializer,
                     {
-                        typetag::#private::serde::Serialize::serialize(self as &dyn #object #ty_generics, serializer)
+                        #typetag::#private::serde::Serialize::serialize(self as &dyn #ser_object, serializer)
                     }
                 }
             });
         }
     }
 
-    if mode.de {
-        let registry = build_registry(&input);
+    expanded.extend(inherent_impl(
+        &input,
+        &object_assoc_types,
+        &sized_assoc_types,
+        args.rename_all,
+        args.namespace,
+        typetag,
+        mode,
+    ));
 
+    if mode.de {
         let is_send = has_supertrait(&input, "Send");
         let is_sync = has_supertrait(&input, "Sync");
         let (strictest, others) = match (is_send, is_sync) {
@@ -78,39 +168,139 @@ pub(crate) fn expand(args: TraitArgs, mut input: ItemTrait, mode: Mode) -> Token
             ),
         };
 
-        expanded.extend(quote! {
-            #registry
+        let registry_kind = match args.registry {
+            Registry::Inventory => quote!(RegistryInventory),
+            Registry::Manual => quote!(RegistryManual),
+        };
 
-            impl typetag::#private::Strictest for dyn #object {
-                type Object = dyn #object + #strictest;
+        for object in &instantiations {
+            let static_registry = static_registry(
+                object,
+                &args.registry,
+                args.unknown.as_ref(),
+                args.suggestions,
+                args.unit_shorthand,
+                &args.limits,
+                typetag,
+            );
+
+            if let Registry::Inventory = args.registry {
+                expanded.extend(quote! {
+                    #typetag::#private::inventory::collect!(
+                        TypetagRegistration<dyn #object + #strictest>
+                    );
+                });
             }
 
-            #[allow(unknown_lints, non_local_definitions)] // false positive: https://github.com/rust-lang/rust/issues/121621
-            impl<'de> typetag::#private::serde::Deserialize<'de> for typetag::#private::Box<dyn #object + #strictest> {
-                fn deserialize<D>(deserializer: D) -> typetag::#private::Result<Self, D::Error>
-                where
-                    D: typetag::#private::serde::Deserializer<'de>,
-                {
-                    #deserialize_impl
+            expanded.extend(quote! {
+                impl #typetag::#private::Strictest for dyn #object {
+                    type Object = dyn #object + #strictest;
+                }
+
+                impl #typetag::#private::#registry_kind for dyn #object {}
+
+                impl #typetag::#private::Registered for dyn #object {
+                    fn registry() -> &'static #typetag::#private::Registry<dyn #object + #strictest> {
+                        #static_registry
+                    }
+
+                    fn deserialize<'de, D>(
+                        deserializer: D,
+                        scope: #typetag::#private::Scope,
+                    ) -> #typetag::#private::Result<#typetag::#private::Box<dyn #object + #strictest>, D::Error>
+                    where
+                        D: #typetag::#private::serde::Deserializer<'de>,
+                    {
+                        let registry = Self::registry();
+                        #deserialize_impl
+                    }
                 }
-            }
-        });
 
-        for marker_traits in others {
-            expanded.extend(quote! {
                 #[allow(unknown_lints, non_local_definitions)] // false positive: https://github.com/rust-lang/rust/issues/121621
-                impl<'de> typetag::#private::serde::Deserialize<'de> for typetag::#private::Box<dyn #object + #marker_traits> {
-                    fn deserialize<D>(deserializer: D) -> typetag::#private::Result<Self, D::Error>
+                impl<'de> #typetag::#private::serde::Deserialize<'de> for #typetag::#private::Box<dyn #object + #strictest> {
+                    fn deserialize<D>(deserializer: D) -> #typetag::#private::Result<Self, D::Error>
                     where
-                        D: typetag::#private::serde::Deserializer<'de>,
+                        D: #typetag::#private::serde::Deserializer<'de>,
                     {
-                        typetag::#private::Result::Ok(
-                            <typetag::#private::Box<dyn #object + #strictest>
-                                as typetag::#private::serde::Deserialize<'de>>::deserialize(deserializer)?
+                        <dyn #object as #typetag::#private::Registered>::deserialize(
+                            deserializer,
+                            <#typetag::#private::Scope as #typetag::#private::Default>::default(),
                         )
                     }
                 }
             });
+
+            // Impls name every associated type in the trait object type they
+            // register into, including ones that trait objects ignore.
+            if !sized_assoc_types.is_empty() {
+                let (params, bindings) = assoc_type_params(&sized_assoc_types);
+                let generics = Generics {
+                    params: params.into_iter().collect(),
+                    ..Generics::default()
+                };
+                let (impl_generics, _, _) = generics.split_for_impl();
+                let bound_object = with_assoc_types(object, bindings);
+                expanded.extend(quote! {
+                    #[allow(unknown_lints, unused_associated_type_bounds)]
+                    impl #impl_generics #typetag::#private::Strictest for dyn #bound_object {
+                        type Object = <dyn #object as #typetag::#private::Strictest>::Object;
+                    }
+
+                    #[allow(unknown_lints, unused_associated_type_bounds)]
+                    impl #impl_generics #typetag::#private::#registry_kind for dyn #bound_object {}
+
+                    #[allow(unknown_lints, unused_associated_type_bounds)]
+                    impl #impl_generics #typetag::#private::Registered for dyn #bound_object {
+                        fn registry() -> &'static #typetag::#private::Registry<dyn #object + #strictest> {
+                            <dyn #object as #typetag::#private::Registered>::registry()
+                        }
+
+                        fn deserialize<'de, D>(
+                            deserializer: D,
+                            scope: #typetag::#private::Scope,
+                        ) -> #typetag::#private::Result<#typetag::#private::Box<dyn #object + #strictest>, D::Error>
+                        where
+                            D: #typetag::#private::serde::Deserializer<'de>,
+                        {
+                            <dyn #object as #typetag::#private::Registered>::deserialize(deserializer, scope)
+                        }
+                    }
+                });
+            }
+
+            let object_name = input.ident.to_string();
+            for marker_traits in iter::once(&strictest).chain(&others) {
+                expanded.extend(quote! {
+                    impl #typetag::#private::Deserializable for dyn #object + #marker_traits {
+                        type Registered = dyn #object;
+
+                        const NAME: &'static str = #object_name;
+
+                        fn from_strictest(
+                            object: #typetag::#private::Box<dyn #object + #strictest>,
+                        ) -> #typetag::#private::Box<Self> {
+                            object
+                        }
+                    }
+                });
+            }
+
+            for marker_traits in &others {
+                expanded.extend(quote! {
+                    #[allow(unknown_lints, non_local_definitions)] // false positive: https://github.com/rust-lang/rust/issues/121621
+                    impl<'de> #typetag::#private::serde::Deserialize<'de> for #typetag::#private::Box<dyn #object + #marker_traits> {
+                        fn deserialize<D>(deserializer: D) -> #typetag::#private::Result<Self, D::Error>
+                        where
+                            D: #typetag::#private::serde::Deserializer<'de>,
+                        {
+                            #typetag::#private::Result::Ok(
+                                <#typetag::#private::Box<dyn #object + #strictest>
+                                    as #typetag::#private::serde::Deserialize<'de>>::deserialize(deserializer)?
+                            )
+                        }
+                    }
+                });
+            }
         }
     }
 
@@ -124,18 +314,28 @@ pub(crate) fn expand(args: TraitArgs, mut input: ItemTrait, mode: Mode) -> Token
     }
 }
 
-fn augment_trait(input: &mut ItemTrait, mode: Mode) {
+fn augment_trait(input: &mut ItemTrait, typetag: &Path, mode: Mode) {
     if mode.ser {
-        input.supertraits.push(parse_quote!(typetag::Serialize));
+        input.supertraits.push(parse_quote!(#typetag::Serialize));
+
+        input.items.push(parse_quote! {
+            #[doc(hidden)]
+            fn typetag_name(&self) -> &str;
+        });
+
+        input.items.push(parse_quote! {
+            #[doc(hidden)]
+            fn typetag_static_name(&self) -> #typetag::#private::Option<&'static str>;
+        });
 
         input.items.push(parse_quote! {
             #[doc(hidden)]
-            fn typetag_name(&self) -> &'static str;
+            fn typetag_id(&self) -> #typetag::#private::Id;
         });
     }
 
     if mode.de {
-        input.supertraits.push(parse_quote!(typetag::Deserialize));
+        input.supertraits.push(parse_quote!(#typetag::Deserialize));
 
         // Only to catch missing typetag attribute on impl blocks. Not called.
         input.items.push(parse_quote! {
@@ -145,67 +345,160 @@ fn augment_trait(input: &mut ItemTrait, mode: Mode) {
     }
 }
 
-fn build_registry(input: &ItemTrait) -> TokenStream {
+fn inherent_impl(
+    input: &ItemTrait,
+    object_assoc_types: &[TraitItemType],
+    sized_assoc_types: &[TraitItemType],
+    rename_all: Option<RenameRule>,
+    namespace: Option<Namespace>,
+    typetag: &Path,
+    mode: Mode,
+) -> TokenStream {
     let vis = &input.vis;
     let object = &input.ident;
-
-    quote! {
-        type TypetagStrictest = <dyn #object as typetag::#private::Strictest>::Object;
-        type TypetagFn = typetag::#private::DeserializeFn<TypetagStrictest>;
-
-        #vis struct TypetagRegistration<T> {
-            name: &'static str,
-            deserializer: T,
+    let mut generics = input.generics.clone();
+    let (_, ty_generics, _) = input.generics.split_for_impl();
+    let mut bindings = Vec::new();
+    for assoc_types in [object_assoc_types, sized_assoc_types] {
+        let (assoc_params, assoc_bindings) = assoc_type_params(assoc_types);
+        generics.params.extend(assoc_params);
+        bindings.extend(assoc_bindings);
+    }
+    let object = with_assoc_types(&parse_quote!(#object #ty_generics), bindings);
+    let (impl_generics, _, where_clause) = generics.split_for_impl();
+
+    // Impls without an explicit name pass their type name in every case
+    // convention, bare and qualified with their crate and their module, and
+    // the trait picks one.
+    let namespace_index = match namespace {
+        None => 0usize,
+        Some(Namespace::Crate) => 1,
+        Some(Namespace::Module) => 2,
+    };
+    let rename_index = rename_all.unwrap_or(RenameRule::Pascal).index();
+    let mut items = quote! {
+        #[doc(hidden)]
+        #vis const fn typetag_rename(names: [[&'static str; 8]; 3]) -> &'static str {
+            names[#namespace_index][#rename_index]
         }
+    };
+    let mut registration = TokenStream::new();
 
-        typetag::#private::inventory::collect!(TypetagRegistration<TypetagFn>);
-
-        impl dyn #object {
+    if mode.de {
+        registration = quote! {
+            #vis struct TypetagRegistration<T: ?Sized> {
+                registration: #typetag::#private::Registration<T>,
+            }
+        };
+        items.extend(quote! {
             #[doc(hidden)]
-            #vis const fn typetag_register<T>(name: &'static str, deserializer: T) -> TypetagRegistration<T> {
-                TypetagRegistration { name, deserializer }
+            #vis const fn typetag_register<T: ?Sized>(
+                registration: #typetag::#private::Registration<T>,
+            ) -> TypetagRegistration<T> {
+                TypetagRegistration { registration }
             }
+        });
+    }
+
+    quote! {
+        #registration
+
+        #[allow(unknown_lints, unused_associated_type_bounds)]
+        impl #impl_generics dyn #object #where_clause {
+            #items
         }
     }
 }
 
-fn static_registry() -> TokenStream {
+// type Assoc: Bound; -> (__Assoc: Bound, Assoc = __Assoc)
+fn assoc_type_params(
+    assoc_types: &[TraitItemType],
+) -> (Vec<GenericParam>, Vec<(Ident, TokenStream)>) {
+    let mut params = Vec::new();
+    let mut bindings = Vec::new();
+    for assoc in assoc_types {
+        let ident = &assoc.ident;
+        let param = format_ident!("__{}", ident);
+        let colon_token = assoc.colon_token;
+        let bounds = &assoc.bounds;
+        params.push(parse_quote!(#param #colon_token #bounds));
+        bindings.push((ident.clone(), param.into_token_stream()));
+    }
+    (params, bindings)
+}
+
+fn static_registry(
+    object: &Path,
+    registry: &Registry,
+    unknown: Option<&Type>,
+    suggestions: bool,
+    unit_shorthand: bool,
+    limits: &Limits,
+    typetag: &Path,
+) -> TokenStream {
+    let registrations = match registry {
+        Registry::Inventory => quote! {
+            #typetag::#private::inventory::iter::<TypetagRegistration<<dyn #object as #typetag::#private::Strictest>::Object>>
+                .into_iter()
+                .map(|registered| &registered.registration)
+        },
+        Registry::Manual => quote! {
+            <dyn #object as #typetag::#private::ManualRegistry>::REGISTRATIONS.iter()
+        },
+    };
+
+    let unknown = match unknown {
+        Some(unknown) => quote! {
+            #typetag::#private::Option::Some(|unknown| {
+                #typetag::#private::Box::new(
+                    <#unknown as #typetag::#private::From<#typetag::Unknown>>::from(unknown),
+                ) as #typetag::#private::Box<<dyn #object as #typetag::#private::Strictest>::Object>
+            })
+        },
+        None => quote!(#typetag::#private::Option::None),
+    };
+
+    let limit = |limit: &Option<Expr>| match limit {
+        Some(limit) => quote!(#typetag::#private::Option::Some(#limit)),
+        None => quote!(#typetag::#private::Option::None),
+    };
+    let bytes = limit(&limits.bytes);
+    let elements = limit(&limits.elements);
+    let depth = limit(&limits.depth);
+    let recursion = match &limits.recursion {
+        Some(limit) => quote!(#typetag::#private::recursion!(#limit)),
+        None => quote!(#typetag::#private::Option::None),
+    };
+    let limits = quote! {
+        #typetag::#private::Limits {
+            bytes: #bytes,
+            elements: #elements,
+            depth: #depth,
+            recursion: #recursion,
+        }
+    };
+
     quote! {
-        static TYPETAG: typetag::#private::once_cell::race::OnceBox<typetag::#private::Registry<TypetagStrictest>> = typetag::#private::once_cell::race::OnceBox::new();
-        let registry = TYPETAG.get_or_init(|| {
-            let mut map = typetag::#private::BTreeMap::new();
-            let mut names = typetag::#private::Vec::new();
-            for registered in typetag::#private::inventory::iter::<TypetagRegistration<TypetagFn>> {
-                match map.entry(registered.name) {
-                    typetag::#private::btree_map::Entry::Vacant(entry) => {
-                        entry.insert(typetag::#private::Option::Some(registered.deserializer));
-                    }
-                    typetag::#private::btree_map::Entry::Occupied(mut entry) => {
-                        entry.insert(typetag::#private::Option::None);
-                    }
-                }
-                names.push(registered.name);
-            }
-            names.sort_unstable();
-            typetag::#private::Box::new(typetag::#private::Registry { map, names })
-        });
+        static TYPETAG: #typetag::#private::once_cell::race::OnceBox<#typetag::#private::Registry<<dyn #object as #typetag::#private::Strictest>::Object>> = #typetag::#private::once_cell::race::OnceBox::new();
+        TYPETAG.get_or_init(|| {
+            #typetag::#private::Box::new(#typetag::#private::Registry::new(#registrations, #unknown, #suggestions, #unit_shorthand, #limits))
+        })
     }
 }
 
-fn externally_tagged(input: &ItemTrait) -> (TokenStream, TokenStream) {
+fn externally_tagged(input: &ItemTrait, typetag: &Path) -> (TokenStream, TokenStream) {
     let object = &input.ident;
     let object_name = object.to_string();
     let (_, ty_generics, _) = input.generics.split_for_impl();
-    let static_registry = static_registry();
 
     let serialize_impl = quote! {
         let name = <Self as #object #ty_generics>::typetag_name(self);
-        typetag::#private::externally::serialize(serializer, name, self)
+        let id = <Self as #object #ty_generics>::typetag_id(self);
+        #typetag::#private::externally::serialize(serializer, name, id, self)
     };
 
     let deserialize_impl = quote! {
-        #static_registry
-        typetag::#private::externally::deserialize(deserializer, #object_name, registry)
+        #typetag::#private::externally::deserialize(deserializer, #object_name, registry, scope)
     };
 
     (serialize_impl, deserialize_impl)
@@ -215,24 +508,24 @@ fn internally_tagged(
     tag: LitStr,
     default_variant: Option<LitStr>,
     input: &ItemTrait,
+    typetag: &Path,
 ) -> (TokenStream, TokenStream) {
     let object = &input.ident;
     let object_name = object.to_string();
     let (_, ty_generics, _) = input.generics.split_for_impl();
-    let static_registry = static_registry();
     let default_variant_literal = match default_variant {
-        Some(variant) => quote!(typetag::#private::Option::Some(#variant)),
-        None => quote!(typetag::#private::Option::None),
+        Some(variant) => quote!(#typetag::#private::Option::Some(#variant)),
+        None => quote!(#typetag::#private::Option::None),
     };
 
     let serialize_impl = quote! {
         let name = <Self as #object #ty_generics>::typetag_name(self);
-        typetag::#private::internally::serialize(serializer, #tag, name, self)
+        let id = <Self as #object #ty_generics>::typetag_id(self);
+        #typetag::#private::internally::serialize(serializer, #tag, name, id, self)
     };
 
     let deserialize_impl = quote! {
-        #static_registry
-        typetag::#private::internally::deserialize(deserializer, #object_name, #tag, #default_variant_literal, registry)
+        #typetag::#private::internally::deserialize(deserializer, #object_name, #tag, #default_variant_literal, registry, scope)
     };
 
     (serialize_impl, deserialize_impl)
@@ -244,36 +537,71 @@ fn adjacently_tagged(
     default_variant: Option<LitStr>,
     deny_unknown_fields: bool,
     input: &ItemTrait,
+    typetag: &Path,
 ) -> (TokenStream, TokenStream) {
     let object = &input.ident;
     let object_name = object.to_string();
     let (_, ty_generics, _) = input.generics.split_for_impl();
-    let static_registry = static_registry();
     let default_variant_literal = match default_variant {
-        Some(variant) => quote!(typetag::#private::Option::Some(#variant)),
-        None => quote!(typetag::#private::Option::None),
+        Some(variant) => quote!(#typetag::#private::Option::Some(#variant)),
+        None => quote!(#typetag::#private::Option::None),
     };
 
     let serialize_impl = quote! {
         let name = <Self as #object #ty_generics>::typetag_name(self);
-        typetag::#private::adjacently::serialize(serializer, #object_name, #tag, name, #content, self)
+        let id = <Self as #object #ty_generics>::typetag_id(self);
+        #typetag::#private::adjacently::serialize(serializer, #object_name, #tag, name, id, #content, self)
     };
 
     let deserialize_impl = quote! {
-        #static_registry
-        typetag::#private::adjacently::deserialize(
+        #typetag::#private::adjacently::deserialize(
             deserializer,
             #object_name,
             &[#tag, #content],
             #default_variant_literal,
             registry,
             #deny_unknown_fields,
+            scope,
         )
     };
 
     (serialize_impl, deserialize_impl)
 }
 
+fn untagged(input: &ItemTrait, typetag: &Path) -> (TokenStream, TokenStream) {
+    let object = &input.ident;
+    let object_name = object.to_string();
+
+    let serialize_impl = quote! {
+        #typetag::#private::untagged::serialize(serializer, self)
+    };
+
+    let deserialize_impl = quote! {
+        #typetag::#private::untagged::deserialize(deserializer, #object_name, registry, scope)
+    };
+
+    (serialize_impl, deserialize_impl)
+}
+
+fn enum_repr(input: &ItemTrait, typetag: &Path) -> (TokenStream, TokenStream) {
+    let object = &input.ident;
+    let object_name = object.to_string();
+    let (_, ty_generics, _) = input.generics.split_for_impl();
+
+    let serialize_impl = quote! {
+        let name = <Self as #object #ty_generics>::typetag_name(self);
+        let static_name = <Self as #object #ty_generics>::typetag_static_name(self);
+        let id = <Self as #object #ty_generics>::typetag_id(self);
+        #typetag::#private::enumerated::serialize(serializer, #object_name, name, static_name, id, self)
+    };
+
+    let deserialize_impl = quote! {
+        #typetag::#private::enumerated::deserialize(deserializer, #object_name, registry, scope)
+    };
+
+    (serialize_impl, deserialize_impl)
+}
+
 fn has_supertrait(input: &ItemTrait, find: &str) -> bool {
     for supertrait in &input.supertraits {
         if let TypeParamBound::Trait(trait_bound) = supertrait {
@@ -1,7 +1,8 @@
-use crate::content::Content;
-use crate::de::{FnApply, MapLookupVisitor};
+use crate::content::{Budget, ContentSeed};
+use crate::de::{FnApply, MapLookupVisitor, Scope};
+use crate::depth;
 use crate::private::Registry;
-use crate::ser::Wrap;
+use crate::ser::{Id, VariantTag, Wrap};
 use alloc::boxed::Box;
 use core::fmt;
 use serde::de::{
@@ -14,7 +15,8 @@ pub fn serialize<S, T>(
     serializer: S,
     trait_object: &'static str,
     tag: &'static str,
-    variant: &'static str,
+    variant: &str,
+    id: Id,
     content: &'static str,
     concrete: &T,
 ) -> Result<S::Ok, S::Error>
@@ -22,8 +24,9 @@ where
     S: Serializer,
     T: ?Sized + erased_serde::Serialize,
 {
+    let variant = VariantTag::new(variant, id, serializer.is_human_readable());
     let mut ser = serializer.serialize_struct(trait_object, 2)?;
-    ser.serialize_field(tag, variant)?;
+    ser.serialize_field(tag, &variant)?;
     ser.serialize_field(content, &Wrap(concrete))?;
     ser.end()
 }
@@ -35,36 +38,56 @@ pub fn deserialize<'de, D, T>(
     default_variant: Option<&'static str>,
     registry: &'static Registry<T>,
     deny_unknown_fields: bool,
+    scope: Scope,
 ) -> Result<Box<T>, D::Error>
 where
     D: Deserializer<'de>,
     T: ?Sized,
 {
+    let _depth = depth::enter(trait_object, &registry.limits)?;
     let visitor = TaggedVisitor {
         trait_object,
         field_names,
         default_variant,
         registry,
         deny_unknown_fields,
+        scope,
     };
-    deserializer.deserialize_struct(trait_object, field_names, visitor)
+    if registry.unit_shorthand && deserializer.is_human_readable() {
+        deserializer.deserialize_any(visitor)
+    } else {
+        deserializer.deserialize_struct(trait_object, field_names, visitor)
+    }
 }
 
-struct TaggedVisitor<T: ?Sized + 'static> {
+struct TaggedVisitor<'a, T: ?Sized + 'static> {
     trait_object: &'static str,
     field_names: &'static [&'static str; 2], // [tag, content]
     default_variant: Option<&'static str>,
     registry: &'static Registry<T>,
     deny_unknown_fields: bool,
+    scope: Scope<'a>,
 }
 
-impl<'de, T: ?Sized> Visitor<'de> for TaggedVisitor<T> {
+impl<'de, 'a, T: ?Sized> Visitor<'de> for TaggedVisitor<'a, T> {
     type Value = Box<T>;
 
     fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
         write!(formatter, "dyn {}", self.trait_object)
     }
 
+    fn visit_str<E>(self, tag: &str) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        let map_lookup = MapLookupVisitor {
+            expected: &self,
+            registry: self.registry,
+            scope: self.scope,
+        };
+        map_lookup.unit_shorthand(self.trait_object, tag)
+    }
+
     fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
     where
         A: MapAccess<'de>,
@@ -72,6 +95,7 @@ impl<'de, T: ?Sized> Visitor<'de> for TaggedVisitor<T> {
         let map_lookup = MapLookupVisitor {
             expected: &self,
             registry: self.registry,
+            scope: self.scope,
         };
 
         let field_seed = TagContentOtherFieldVisitor {
@@ -99,7 +123,7 @@ impl<'de, T: ?Sized> Visitor<'de> for TaggedVisitor<T> {
             // First key is the tag.
             Some(TagOrContentField::Tag) => {
                 // Parse the tag.
-                let deserialize_fn = map.next_value_seed(map_lookup)?;
+                let variant = map.next_value_seed(map_lookup)?;
                 // Visit the second key.
                 match next_relevant_key(&mut map)? {
                     // Second key is a duplicate of the tag.
@@ -108,12 +132,20 @@ impl<'de, T: ?Sized> Visitor<'de> for TaggedVisitor<T> {
                     }
                     // Second key is the content.
                     Some(TagOrContentField::Content) => {
-                        let fn_apply = FnApply { deserialize_fn };
+                        let fn_apply = FnApply {
+                            trait_object: self.trait_object,
+                            variant,
+                            scope: self.scope,
+                        };
                         map.next_value_seed(fn_apply)?
                     }
                     // There is no second key; might be okay if the we have a unit variant.
                     None => {
-                        let fn_apply = FnApply { deserialize_fn };
+                        let fn_apply = FnApply {
+                            trait_object: self.trait_object,
+                            variant,
+                            scope: self.scope,
+                        };
                         let unit = ().into_deserializer();
                         return fn_apply.deserialize(unit);
                     }
@@ -122,14 +154,19 @@ impl<'de, T: ?Sized> Visitor<'de> for TaggedVisitor<T> {
             // First key is the content.
             Some(TagOrContentField::Content) => {
                 // Buffer up the content.
-                let content = map.next_value::<Content>()?;
+                let budget = Budget::new(self.trait_object, self.registry.limits);
+                let content = map.next_value_seed(ContentSeed::new(&budget))?;
                 // Visit the second key.
                 match next_relevant_key(&mut map)? {
                     // Second key is the tag.
                     Some(TagOrContentField::Tag) => {
                         // Parse the tag.
-                        let deserialize_fn = map.next_value_seed(map_lookup)?;
-                        let fn_apply = FnApply { deserialize_fn };
+                        let variant = map.next_value_seed(map_lookup)?;
+                        let fn_apply = FnApply {
+                            trait_object: self.trait_object,
+                            variant,
+                            scope: self.scope,
+                        };
                         let content = content.into_deserializer();
                         fn_apply.deserialize(content)?
                     }
@@ -140,8 +177,12 @@ impl<'de, T: ?Sized> Visitor<'de> for TaggedVisitor<T> {
                     // There is no second key.
                     None => {
                         if let Some(variant) = self.default_variant {
-                            let deserialize_fn = map_lookup.visit_str(variant)?;
-                            let fn_apply = FnApply { deserialize_fn };
+                            let variant = map_lookup.visit_str(variant)?;
+                            let fn_apply = FnApply {
+                                trait_object: self.trait_object,
+                                variant,
+                                scope: self.scope,
+                            };
                             let content = content.into_deserializer();
                             fn_apply.deserialize(content)?
                         } else {
@@ -176,15 +217,20 @@ impl<'de, T: ?Sized> Visitor<'de> for TaggedVisitor<T> {
         let map_lookup = MapLookupVisitor {
             expected: &self,
             registry: self.registry,
+            scope: self.scope,
         };
 
         // Visit the first element - the tag.
-        let Some(deserialize_fn) = seq.next_element_seed(map_lookup)? else {
+        let Some(variant) = seq.next_element_seed(map_lookup)? else {
             return Err(de::Error::invalid_length(0, &self));
         };
 
         // Visit the second element - the content.
-        let fn_apply = FnApply { deserialize_fn };
+        let fn_apply = FnApply {
+            trait_object: self.trait_object,
+            variant,
+            scope: self.scope,
+        };
         match seq.next_element_seed(fn_apply)? {
             Some(ret) => Ok(ret),
             None => Err(de::Error::invalid_length(1, &self)),
@@ -1,6 +1,8 @@
+use alloc::borrow::ToOwned;
 use alloc::boxed::Box;
 use alloc::string::String;
 use alloc::vec::Vec;
+use core::cell::Cell;
 use core::fmt;
 use core::marker::PhantomData;
 use core::str;
@@ -9,6 +11,7 @@ use serde::de::{
     IntoDeserializer, MapAccess, SeqAccess, Unexpected, VariantAccess, Visitor,
 };
 use serde::forward_to_deserialize_any;
+use serde::ser::{Serialize, SerializeMap, SerializeSeq, Serializer};
 
 // Used from generated code to buffer the contents of the Deserializer when
 // deserializing internally and adjacently tagged trait objects.
@@ -20,11 +23,13 @@ pub enum Content<'de> {
     U16(u16),
     U32(u32),
     U64(u64),
+    U128(u128),
 
     I8(i8),
     I16(i16),
     I32(i32),
     I64(i64),
+    I128(i128),
 
     F32(f32),
     F64(f64),
@@ -52,10 +57,12 @@ impl<'de> Content<'de> {
             Content::U16(n) => Unexpected::Unsigned(u64::from(n)),
             Content::U32(n) => Unexpected::Unsigned(u64::from(n)),
             Content::U64(n) => Unexpected::Unsigned(n),
+            Content::U128(_) => Unexpected::Other("u128"),
             Content::I8(n) => Unexpected::Signed(i64::from(n)),
             Content::I16(n) => Unexpected::Signed(i64::from(n)),
             Content::I32(n) => Unexpected::Signed(i64::from(n)),
             Content::I64(n) => Unexpected::Signed(n),
+            Content::I128(_) => Unexpected::Other("i128"),
             Content::F32(f) => Unexpected::Float(f64::from(f)),
             Content::F64(f) => Unexpected::Float(f),
             Content::Char(c) => Unexpected::Char(c),
@@ -70,25 +77,248 @@ impl<'de> Content<'de> {
             Content::Map(_) => Unexpected::Map,
         }
     }
+
+    pub fn into_owned(self) -> Content<'static> {
+        match self {
+            Content::Bool(b) => Content::Bool(b),
+            Content::U8(n) => Content::U8(n),
+            Content::U16(n) => Content::U16(n),
+            Content::U32(n) => Content::U32(n),
+            Content::U64(n) => Content::U64(n),
+            Content::U128(n) => Content::U128(n),
+            Content::I8(n) => Content::I8(n),
+            Content::I16(n) => Content::I16(n),
+            Content::I32(n) => Content::I32(n),
+            Content::I64(n) => Content::I64(n),
+            Content::I128(n) => Content::I128(n),
+            Content::F32(f) => Content::F32(f),
+            Content::F64(f) => Content::F64(f),
+            Content::Char(c) => Content::Char(c),
+            Content::String(s) => Content::String(s),
+            Content::Str(s) => Content::String(s.to_owned()),
+            Content::ByteBuf(b) => Content::ByteBuf(b),
+            Content::Bytes(b) => Content::ByteBuf(b.to_owned()),
+            Content::None => Content::None,
+            Content::Some(content) => Content::Some(Box::new(content.into_owned())),
+            Content::Unit => Content::Unit,
+            Content::Newtype(content) => Content::Newtype(Box::new(content.into_owned())),
+            Content::Seq(seq) => Content::Seq(seq.into_iter().map(Content::into_owned).collect()),
+            Content::Map(map) => Content::Map(
+                map.into_iter()
+                    .map(|(k, v)| (k.into_owned(), v.into_owned()))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+// Used to re-serialize the buffered contents of an unrecognized trait object.
+impl<'de> Serialize for Content<'de> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self {
+            Content::Bool(b) => serializer.serialize_bool(*b),
+            Content::U8(n) => serializer.serialize_u8(*n),
+            Content::U16(n) => serializer.serialize_u16(*n),
+            Content::U32(n) => serializer.serialize_u32(*n),
+            Content::U64(n) => serializer.serialize_u64(*n),
+            Content::U128(n) => serializer.serialize_u128(*n),
+            Content::I8(n) => serializer.serialize_i8(*n),
+            Content::I16(n) => serializer.serialize_i16(*n),
+            Content::I32(n) => serializer.serialize_i32(*n),
+            Content::I64(n) => serializer.serialize_i64(*n),
+            Content::I128(n) => serializer.serialize_i128(*n),
+            Content::F32(f) => serializer.serialize_f32(*f),
+            Content::F64(f) => serializer.serialize_f64(*f),
+            Content::Char(c) => serializer.serialize_char(*c),
+            Content::String(s) => serializer.serialize_str(s),
+            Content::Str(s) => serializer.serialize_str(s),
+            Content::ByteBuf(b) => serializer.serialize_bytes(b),
+            Content::Bytes(b) => serializer.serialize_bytes(b),
+            Content::None => serializer.serialize_none(),
+            Content::Some(content) => serializer.serialize_some(&**content),
+            Content::Unit => serializer.serialize_unit(),
+            Content::Newtype(content) => serializer.serialize_newtype_struct("", &**content),
+            Content::Seq(seq) => {
+                let mut ser = serializer.serialize_seq(Some(seq.len()))?;
+                for element in seq {
+                    ser.serialize_element(element)?;
+                }
+                ser.end()
+            }
+            Content::Map(map) => {
+                let mut ser = serializer.serialize_map(Some(map.len()))?;
+                for (k, v) in map {
+                    ser.serialize_entry(k, v)?;
+                }
+                ser.end()
+            }
+        }
+    }
 }
 
 impl<'de> Deserialize<'de> for Content<'de> {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        ContentSeed {
+            budget: None,
+            depth: 0,
+        }
+        .deserialize(deserializer)
+    }
+}
+
+// Bounds on what a trait object may buffer while looking for its tag.
+#[derive(Copy, Clone, Default)]
+pub struct Limits {
+    pub bytes: Option<usize>,
+    pub elements: Option<usize>,
+    pub depth: Option<usize>,
+    pub recursion: Option<usize>,
+}
+
+// Running totals against the Limits of one trait object being deserialized.
+pub struct Budget {
+    trait_object: &'static str,
+    limits: Limits,
+    bytes: Cell<usize>,
+    elements: Cell<usize>,
+}
+
+impl Budget {
+    pub fn new(trait_object: &'static str, limits: Limits) -> Self {
+        Budget {
+            trait_object,
+            limits,
+            bytes: Cell::new(0),
+            elements: Cell::new(0),
+        }
+    }
+
+    // Counts the bytes of a string or byte array, borrowed or not, so that
+    // limits do not depend on whether the format can lend out its input.
+    pub fn charge_bytes<E>(&self, len: usize) -> Result<(), E>
+    where
+        E: de::Error,
+    {
+        let bytes = self.bytes.get().saturating_add(len);
+        self.bytes.set(bytes);
+        match self.limits.bytes {
+            Some(limit) if bytes > limit => Err(self.exceeded(limit, "bytes")),
+            _ => Ok(()),
+        }
+    }
+
+    // A string key buffered outside of a Content map, charged like one inside.
+    pub fn charge_key<E>(&self, len: usize) -> Result<(), E>
+    where
+        E: de::Error,
+    {
+        self.charge_bytes(len)?;
+        self.charge_element()
+    }
+
+    fn charge_element<E>(&self) -> Result<(), E>
+    where
+        E: de::Error,
+    {
+        let elements = self.elements.get() + 1;
+        self.elements.set(elements);
+        match self.limits.elements {
+            Some(limit) if elements > limit => Err(self.exceeded(limit, "elements")),
+            _ => Ok(()),
+        }
+    }
+
+    fn exceeded<E>(&self, limit: usize, unit: &str) -> E
+    where
+        E: de::Error,
+    {
+        de::Error::custom(format_args!(
+            "buffered content of dyn {} exceeds the limit of {} {}",
+            self.trait_object, limit, unit,
+        ))
+    }
+}
+
+// Deserializes Content, charging everything buffered to a budget if there is
+// one. Depth counts the sequences, maps, options and newtypes enclosing the
+// value.
+#[derive(Copy, Clone)]
+pub struct ContentSeed<'b> {
+    budget: Option<&'b Budget>,
+    depth: usize,
+}
+
+impl<'b> ContentSeed<'b> {
+    pub fn new(budget: &'b Budget) -> Self {
+        ContentSeed {
+            budget: Some(budget),
+            depth: 0,
+        }
+    }
+
+    pub fn nested<E>(self) -> Result<Self, E>
+    where
+        E: de::Error,
+    {
+        let depth = self.depth + 1;
+        if let Some(budget) = self.budget {
+            match budget.limits.depth {
+                Some(limit) if depth > limit => {
+                    return Err(budget.exceeded(limit, "levels of nesting"));
+                }
+                _ => {}
+            }
+        }
+        Ok(ContentSeed {
+            budget: self.budget,
+            depth,
+        })
+    }
+
+    fn charge_bytes<E>(self, len: usize) -> Result<(), E>
+    where
+        E: de::Error,
+    {
+        match self.budget {
+            Some(budget) => budget.charge_bytes(len),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<'de, 'b> DeserializeSeed<'de> for ContentSeed<'b> {
+    type Value = Content<'de>;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
     where
         D: Deserializer<'de>,
     {
         // Untagged and internally tagged enums are only supported in
         // self-describing formats.
-        let visitor = ContentVisitor { value: PhantomData };
-        deserializer.deserialize_any(visitor)
+        let visitor = ContentVisitor {
+            seed: self,
+            value: PhantomData,
+        };
+        let content = deserializer.deserialize_any(visitor)?;
+        if let Some(budget) = self.budget {
+            budget.charge_element()?;
+        }
+        Ok(content)
     }
 }
 
-struct ContentVisitor<'de> {
+struct ContentVisitor<'de, 'b> {
+    seed: ContentSeed<'b>,
     value: PhantomData<Content<'de>>,
 }
 
-impl<'de> Visitor<'de> for ContentVisitor<'de> {
+impl<'de, 'b> Visitor<'de> for ContentVisitor<'de, 'b> {
     type Value = Content<'de>;
 
     fn expecting(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
@@ -130,6 +360,13 @@ impl<'de> Visitor<'de> for ContentVisitor<'de> {
         Ok(Content::I64(value))
     }
 
+    fn visit_i128<F>(self, value: i128) -> Result<Self::Value, F>
+    where
+        F: de::Error,
+    {
+        Ok(Content::I128(value))
+    }
+
     fn visit_u8<F>(self, value: u8) -> Result<Self::Value, F>
     where
         F: de::Error,
@@ -158,6 +395,13 @@ impl<'de> Visitor<'de> for ContentVisitor<'de> {
         Ok(Content::U64(value))
     }
 
+    fn visit_u128<F>(self, value: u128) -> Result<Self::Value, F>
+    where
+        F: de::Error,
+    {
+        Ok(Content::U128(value))
+    }
+
     fn visit_f32<F>(self, value: f32) -> Result<Self::Value, F>
     where
         F: de::Error,
@@ -183,6 +427,7 @@ impl<'de> Visitor<'de> for ContentVisitor<'de> {
     where
         F: de::Error,
     {
+        self.seed.charge_bytes(value.len())?;
         Ok(Content::String(value.into()))
     }
 
@@ -190,6 +435,7 @@ impl<'de> Visitor<'de> for ContentVisitor<'de> {
     where
         F: de::Error,
     {
+        self.seed.charge_bytes(value.len())?;
         Ok(Content::Str(value))
     }
 
@@ -197,6 +443,7 @@ impl<'de> Visitor<'de> for ContentVisitor<'de> {
     where
         F: de::Error,
     {
+        self.seed.charge_bytes(value.len())?;
         Ok(Content::String(value))
     }
 
@@ -204,6 +451,7 @@ impl<'de> Visitor<'de> for ContentVisitor<'de> {
     where
         F: de::Error,
     {
+        self.seed.charge_bytes(value.len())?;
         Ok(Content::ByteBuf(value.into()))
     }
 
@@ -211,6 +459,7 @@ impl<'de> Visitor<'de> for ContentVisitor<'de> {
     where
         F: de::Error,
     {
+        self.seed.charge_bytes(value.len())?;
         Ok(Content::Bytes(value))
     }
 
@@ -218,6 +467,7 @@ impl<'de> Visitor<'de> for ContentVisitor<'de> {
     where
         F: de::Error,
     {
+        self.seed.charge_bytes(value.len())?;
         Ok(Content::ByteBuf(value))
     }
 
@@ -239,22 +489,27 @@ impl<'de> Visitor<'de> for ContentVisitor<'de> {
     where
         D: Deserializer<'de>,
     {
-        Deserialize::deserialize(deserializer).map(|v| Content::Some(Box::new(v)))
+        let seed = self.seed.nested()?;
+        seed.deserialize(deserializer)
+            .map(|v| Content::Some(Box::new(v)))
     }
 
     fn visit_newtype_struct<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
     where
         D: Deserializer<'de>,
     {
-        Deserialize::deserialize(deserializer).map(|v| Content::Newtype(Box::new(v)))
+        let seed = self.seed.nested()?;
+        seed.deserialize(deserializer)
+            .map(|v| Content::Newtype(Box::new(v)))
     }
 
     fn visit_seq<V>(self, mut visitor: V) -> Result<Self::Value, V::Error>
     where
         V: SeqAccess<'de>,
     {
+        let seed = self.seed.nested()?;
         let mut vec = Vec::new();
-        while let Some(e) = visitor.next_element()? {
+        while let Some(e) = visitor.next_element_seed(seed)? {
             vec.push(e);
         }
         Ok(Content::Seq(vec))
@@ -264,8 +519,9 @@ impl<'de> Visitor<'de> for ContentVisitor<'de> {
     where
         V: MapAccess<'de>,
     {
+        let seed = self.seed.nested()?;
         let mut vec = Vec::new();
-        while let Some(kv) = visitor.next_entry()? {
+        while let Some(kv) = visitor.next_entry_seed(seed, seed)? {
             vec.push(kv);
         }
         Ok(Content::Map(vec))
@@ -325,10 +581,12 @@ where
             Content::U16(v) => visitor.visit_u16(v),
             Content::U32(v) => visitor.visit_u32(v),
             Content::U64(v) => visitor.visit_u64(v),
+            Content::U128(v) => visitor.visit_u128(v),
             Content::I8(v) => visitor.visit_i8(v),
             Content::I16(v) => visitor.visit_i16(v),
             Content::I32(v) => visitor.visit_i32(v),
             Content::I64(v) => visitor.visit_i64(v),
+            Content::I128(v) => visitor.visit_i128(v),
             _ => Err(self.invalid_type(&visitor)),
         }
     }
@@ -379,10 +637,12 @@ where
             Content::U16(v) => visitor.visit_u16(v),
             Content::U32(v) => visitor.visit_u32(v),
             Content::U64(v) => visitor.visit_u64(v),
+            Content::U128(v) => visitor.visit_u128(v),
             Content::I8(v) => visitor.visit_i8(v),
             Content::I16(v) => visitor.visit_i16(v),
             Content::I32(v) => visitor.visit_i32(v),
             Content::I64(v) => visitor.visit_i64(v),
+            Content::I128(v) => visitor.visit_i128(v),
             Content::F32(v) => visitor.visit_f32(v),
             Content::F64(v) => visitor.visit_f64(v),
             Content::Char(v) => visitor.visit_char(v),
@@ -437,6 +697,13 @@ where
         self.deserialize_integer(visitor)
     }
 
+    fn deserialize_i128<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_integer(visitor)
+    }
+
     fn deserialize_u8<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
@@ -465,6 +732,13 @@ where
         self.deserialize_integer(visitor)
     }
 
+    fn deserialize_u128<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_integer(visitor)
+    }
+
     fn deserialize_f32<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
@@ -992,3 +1266,565 @@ where
         tuple_struct map struct enum identifier ignored_any
     }
 }
+
+// Deserializes a borrowed Content, so that the same buffered content can be
+// tried against several impls without copying it for each one.
+pub struct ContentRefDeserializer<'a, 'de, E> {
+    content: &'a Content<'de>,
+    err: PhantomData<E>,
+}
+
+impl<'a, 'de, E> ContentRefDeserializer<'a, 'de, E>
+where
+    E: de::Error,
+{
+    pub fn new(content: &'a Content<'de>) -> Self {
+        ContentRefDeserializer {
+            content,
+            err: PhantomData,
+        }
+    }
+
+    fn invalid_type(self, exp: &dyn Expected) -> E {
+        de::Error::invalid_type(self.content.unexpected(), exp)
+    }
+
+    fn deserialize_integer<V>(self, visitor: V) -> Result<V::Value, E>
+    where
+        V: Visitor<'de>,
+    {
+        match *self.content {
+            Content::U8(v) => visitor.visit_u8(v),
+            Content::U16(v) => visitor.visit_u16(v),
+            Content::U32(v) => visitor.visit_u32(v),
+            Content::U64(v) => visitor.visit_u64(v),
+            Content::U128(v) => visitor.visit_u128(v),
+            Content::I8(v) => visitor.visit_i8(v),
+            Content::I16(v) => visitor.visit_i16(v),
+            Content::I32(v) => visitor.visit_i32(v),
+            Content::I64(v) => visitor.visit_i64(v),
+            Content::I128(v) => visitor.visit_i128(v),
+            _ => Err(self.invalid_type(&visitor)),
+        }
+    }
+}
+
+impl<'a, 'de, E> Copy for ContentRefDeserializer<'a, 'de, E> {}
+
+impl<'a, 'de, E> Clone for ContentRefDeserializer<'a, 'de, E> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, 'de, E> IntoDeserializer<'de, E> for ContentRefDeserializer<'a, 'de, E>
+where
+    E: de::Error,
+{
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self::Deserializer {
+        self
+    }
+}
+
+fn visit_content_seq_ref<'a, 'de, V, E>(
+    content: &'a [Content<'de>],
+    visitor: V,
+) -> Result<V::Value, E>
+where
+    V: Visitor<'de>,
+    E: de::Error,
+{
+    let seq = content.iter().map(ContentRefDeserializer::new);
+    let mut seq_visitor = value::SeqDeserializer::new(seq);
+    let value = visitor.visit_seq(&mut seq_visitor)?;
+    seq_visitor.end()?;
+    Ok(value)
+}
+
+fn visit_content_map_ref<'a, 'de, V, E>(
+    content: &'a [(Content<'de>, Content<'de>)],
+    visitor: V,
+) -> Result<V::Value, E>
+where
+    V: Visitor<'de>,
+    E: de::Error,
+{
+    let map = content.iter().map(|(k, v)| {
+        (
+            ContentRefDeserializer::new(k),
+            ContentRefDeserializer::new(v),
+        )
+    });
+    let mut map_visitor = value::MapDeserializer::new(map);
+    let value = visitor.visit_map(&mut map_visitor)?;
+    map_visitor.end()?;
+    Ok(value)
+}
+
+impl<'a, 'de, E> Deserializer<'de> for ContentRefDeserializer<'a, 'de, E>
+where
+    E: de::Error,
+{
+    type Error = E;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match *self.content {
+            Content::Bool(v) => visitor.visit_bool(v),
+            Content::U8(v) => visitor.visit_u8(v),
+            Content::U16(v) => visitor.visit_u16(v),
+            Content::U32(v) => visitor.visit_u32(v),
+            Content::U64(v) => visitor.visit_u64(v),
+            Content::U128(v) => visitor.visit_u128(v),
+            Content::I8(v) => visitor.visit_i8(v),
+            Content::I16(v) => visitor.visit_i16(v),
+            Content::I32(v) => visitor.visit_i32(v),
+            Content::I64(v) => visitor.visit_i64(v),
+            Content::I128(v) => visitor.visit_i128(v),
+            Content::F32(v) => visitor.visit_f32(v),
+            Content::F64(v) => visitor.visit_f64(v),
+            Content::Char(v) => visitor.visit_char(v),
+            Content::String(ref v) => visitor.visit_str(v),
+            Content::Str(v) => visitor.visit_borrowed_str(v),
+            Content::ByteBuf(ref v) => visitor.visit_bytes(v),
+            Content::Bytes(v) => visitor.visit_borrowed_bytes(v),
+            Content::Unit => visitor.visit_unit(),
+            Content::None => visitor.visit_none(),
+            Content::Some(ref v) => visitor.visit_some(ContentRefDeserializer::new(v)),
+            Content::Newtype(ref v) => visitor.visit_newtype_struct(ContentRefDeserializer::new(v)),
+            Content::Seq(ref v) => visit_content_seq_ref(v, visitor),
+            Content::Map(ref v) => visit_content_map_ref(v, visitor),
+        }
+    }
+
+    fn deserialize_bool<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match *self.content {
+            Content::Bool(v) => visitor.visit_bool(v),
+            _ => Err(self.invalid_type(&visitor)),
+        }
+    }
+
+    fn deserialize_i8<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_integer(visitor)
+    }
+
+    fn deserialize_i16<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_integer(visitor)
+    }
+
+    fn deserialize_i32<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_integer(visitor)
+    }
+
+    fn deserialize_i64<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_integer(visitor)
+    }
+
+    fn deserialize_i128<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_integer(visitor)
+    }
+
+    fn deserialize_u8<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_integer(visitor)
+    }
+
+    fn deserialize_u16<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_integer(visitor)
+    }
+
+    fn deserialize_u32<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_integer(visitor)
+    }
+
+    fn deserialize_u64<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_integer(visitor)
+    }
+
+    fn deserialize_u128<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_integer(visitor)
+    }
+
+    fn deserialize_f32<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match *self.content {
+            Content::F32(v) => visitor.visit_f32(v),
+            Content::F64(v) => visitor.visit_f64(v),
+            Content::U64(v) => visitor.visit_u64(v),
+            Content::I64(v) => visitor.visit_i64(v),
+            _ => Err(self.invalid_type(&visitor)),
+        }
+    }
+
+    fn deserialize_f64<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match *self.content {
+            Content::F64(v) => visitor.visit_f64(v),
+            Content::U64(v) => visitor.visit_u64(v),
+            Content::I64(v) => visitor.visit_i64(v),
+            _ => Err(self.invalid_type(&visitor)),
+        }
+    }
+
+    fn deserialize_char<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match *self.content {
+            Content::Char(v) => visitor.visit_char(v),
+            Content::String(ref v) => visitor.visit_str(v),
+            Content::Str(v) => visitor.visit_borrowed_str(v),
+            _ => Err(self.invalid_type(&visitor)),
+        }
+    }
+
+    fn deserialize_str<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match *self.content {
+            Content::String(ref v) => visitor.visit_str(v),
+            Content::Str(v) => visitor.visit_borrowed_str(v),
+            Content::ByteBuf(ref v) => visitor.visit_bytes(v),
+            Content::Bytes(v) => visitor.visit_borrowed_bytes(v),
+            _ => Err(self.invalid_type(&visitor)),
+        }
+    }
+
+    fn deserialize_string<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_bytes<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match *self.content {
+            Content::String(ref v) => visitor.visit_str(v),
+            Content::Str(v) => visitor.visit_borrowed_str(v),
+            Content::ByteBuf(ref v) => visitor.visit_bytes(v),
+            Content::Bytes(v) => visitor.visit_borrowed_bytes(v),
+            Content::Seq(ref v) => visit_content_seq_ref(v, visitor),
+            _ => Err(self.invalid_type(&visitor)),
+        }
+    }
+
+    fn deserialize_byte_buf<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_bytes(visitor)
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match *self.content {
+            Content::None => visitor.visit_none(),
+            Content::Some(ref v) => visitor.visit_some(ContentRefDeserializer::new(v)),
+            Content::Unit => visitor.visit_unit(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_unit<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match *self.content {
+            Content::Unit => visitor.visit_unit(),
+            _ => Err(self.invalid_type(&visitor)),
+        }
+    }
+
+    fn deserialize_unit_struct<V>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match *self.content {
+            // Same special case as in ContentDeserializer.
+            Content::Map(ref v) if v.is_empty() => visitor.visit_unit(),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_newtype_struct<V>(self, _name: &str, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match *self.content {
+            Content::Newtype(ref v) => visitor.visit_newtype_struct(ContentRefDeserializer::new(v)),
+            _ => visitor.visit_newtype_struct(self),
+        }
+    }
+
+    fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match *self.content {
+            Content::Seq(ref v) => visit_content_seq_ref(v, visitor),
+            _ => Err(self.invalid_type(&visitor)),
+        }
+    }
+
+    fn deserialize_tuple<V>(self, _len: usize, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V>(
+        self,
+        _name: &'static str,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_map<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match *self.content {
+            Content::Map(ref v) => visit_content_map_ref(v, visitor),
+            _ => Err(self.invalid_type(&visitor)),
+        }
+    }
+
+    fn deserialize_struct<V>(
+        self,
+        _name: &'static str,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match *self.content {
+            Content::Seq(ref v) => visit_content_seq_ref(v, visitor),
+            Content::Map(ref v) => visit_content_map_ref(v, visitor),
+            _ => Err(self.invalid_type(&visitor)),
+        }
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        _name: &str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let (variant, value) = match *self.content {
+            Content::Map(ref value) => {
+                let mut iter = value.iter();
+                let Some((variant, value)) = iter.next() else {
+                    return Err(de::Error::invalid_value(
+                        Unexpected::Map,
+                        &"map with a single key",
+                    ));
+                };
+                // enums are encoded in json as maps with a single key:value pair
+                if iter.next().is_some() {
+                    return Err(de::Error::invalid_value(
+                        Unexpected::Map,
+                        &"map with a single key",
+                    ));
+                }
+                (variant, Some(value))
+            }
+            ref s @ Content::String(_) | ref s @ Content::Str(_) => (s, None),
+            ref other => {
+                return Err(de::Error::invalid_type(
+                    other.unexpected(),
+                    &"string or map",
+                ));
+            }
+        };
+
+        visitor.visit_enum(EnumRefDeserializer {
+            variant,
+            value,
+            err: PhantomData,
+        })
+    }
+
+    fn deserialize_identifier<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match *self.content {
+            Content::String(ref v) => visitor.visit_str(v),
+            Content::Str(v) => visitor.visit_borrowed_str(v),
+            Content::ByteBuf(ref v) => visitor.visit_bytes(v),
+            Content::Bytes(v) => visitor.visit_borrowed_bytes(v),
+            Content::U8(v) => visitor.visit_u8(v),
+            _ => Err(self.invalid_type(&visitor)),
+        }
+    }
+
+    fn deserialize_ignored_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_unit()
+    }
+}
+
+struct EnumRefDeserializer<'a, 'de, E>
+where
+    E: de::Error,
+{
+    variant: &'a Content<'de>,
+    value: Option<&'a Content<'de>>,
+    err: PhantomData<E>,
+}
+
+impl<'a, 'de, E> EnumAccess<'de> for EnumRefDeserializer<'a, 'de, E>
+where
+    E: de::Error,
+{
+    type Error = E;
+    type Variant = VariantRefDeserializer<'a, 'de, Self::Error>;
+
+    fn variant_seed<V>(self, seed: V) -> Result<(V::Value, Self::Variant), E>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        let visitor = VariantRefDeserializer {
+            value: self.value,
+            err: PhantomData,
+        };
+        seed.deserialize(ContentRefDeserializer::new(self.variant))
+            .map(|v| (v, visitor))
+    }
+}
+
+struct VariantRefDeserializer<'a, 'de, E>
+where
+    E: de::Error,
+{
+    value: Option<&'a Content<'de>>,
+    err: PhantomData<E>,
+}
+
+impl<'a, 'de, E> VariantAccess<'de> for VariantRefDeserializer<'a, 'de, E>
+where
+    E: de::Error,
+{
+    type Error = E;
+
+    fn unit_variant(self) -> Result<(), E> {
+        match self.value {
+            Some(value) => Deserialize::deserialize(ContentRefDeserializer::new(value)),
+            None => Ok(()),
+        }
+    }
+
+    fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value, E>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        match self.value {
+            Some(value) => seed.deserialize(ContentRefDeserializer::new(value)),
+            None => Err(de::Error::invalid_type(
+                Unexpected::UnitVariant,
+                &"newtype variant",
+            )),
+        }
+    }
+
+    fn tuple_variant<V>(self, _len: usize, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self.value {
+            Some(Content::Seq(v)) => visit_content_seq_ref(v, visitor),
+            Some(other) => Err(de::Error::invalid_type(
+                other.unexpected(),
+                &"tuple variant",
+            )),
+            None => Err(de::Error::invalid_type(
+                Unexpected::UnitVariant,
+                &"tuple variant",
+            )),
+        }
+    }
+
+    fn struct_variant<V>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self.value {
+            Some(Content::Map(v)) => visit_content_map_ref(v, visitor),
+            Some(Content::Seq(v)) => visit_content_seq_ref(v, visitor),
+            Some(other) => Err(de::Error::invalid_type(
+                other.unexpected(),
+                &"struct variant",
+            )),
+            None => Err(de::Error::invalid_type(
+                Unexpected::UnitVariant,
+                &"struct variant",
+            )),
+        }
+    }
+}
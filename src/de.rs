@@ -1,11 +1,88 @@
-use crate::private::{DeserializeFn, Registry};
+use crate::content::{Budget, ContentSeed, Limits};
+use crate::private::{Registration, Registry, UnknownFn};
+use crate::suggest::UnknownTag;
+use crate::unknown::Unknown;
+use alloc::borrow::ToOwned;
 use alloc::boxed::Box;
+use alloc::string::String;
+use core::any::Any;
 use core::fmt;
-use serde::de::{self, DeserializeSeed, Deserializer, Expected, Visitor};
+use serde::de::value::UnitDeserializer;
+use serde::de::{self, DeserializeSeed, Deserializer, Expected, Unexpected, Visitor};
+
+// The outcome of looking up a tag in the registry.
+pub enum Variant<T: ?Sized + 'static> {
+    Registered(&'static Registration<T>),
+    // Payloads of unknown tags are buffered within the trait's limits.
+    Unknown(String, UnknownFn<T>, Limits),
+}
+
+// What a single deserialization brings beyond the registry: the context passed
+// to typetag::seed, and the policy of typetag::Filtered.
+#[derive(Copy, Clone, Default)]
+pub struct Scope<'a> {
+    pub context: Option<&'a dyn Any>,
+    pub policy: Option<fn(&str) -> bool>,
+}
+
+impl<'a> Scope<'a> {
+    pub fn allows(&self, tag: &str) -> bool {
+        self.policy.map_or(true, |allows| allows(tag))
+    }
+}
 
 pub struct MapLookupVisitor<'a, T: ?Sized + 'static> {
     pub expected: &'a dyn Expected,
     pub registry: &'static Registry<T>,
+    pub scope: Scope<'a>,
+}
+
+impl<'a, T: ?Sized + 'static> MapLookupVisitor<'a, T> {
+    // Formats that are not self-describing need to be told which kind of tag
+    // comes next. Once any impl has an id, compact formats use ids.
+    pub fn expects_id(&self, is_human_readable: bool) -> bool {
+        !is_human_readable && !self.registry.ids.is_empty()
+    }
+
+    // Policies see the name an impl is registered under, whichever alias or id
+    // the input used.
+    fn allowed<E>(&self, registration: &'static Registration<T>) -> Result<Variant<T>, E>
+    where
+        E: de::Error,
+    {
+        if self.scope.allows(registration.name) {
+            Ok(Variant::Registered(registration))
+        } else {
+            Err(self.disallowed(registration.name))
+        }
+    }
+
+    fn disallowed<E>(&self, tag: &str) -> E
+    where
+        E: de::Error,
+    {
+        de::Error::custom(format_args!(
+            "disallowed tag of {}: {:?}",
+            self.expected, tag
+        ))
+    }
+
+    // Traits declared with unit_shorthand accept a bare tag in place of the
+    // whole trait object, for impls that carry no data.
+    pub fn unit_shorthand<E>(self, trait_object: &'static str, tag: &str) -> Result<Box<T>, E>
+    where
+        E: de::Error,
+    {
+        if !self.registry.unit_shorthand {
+            return Err(de::Error::invalid_type(Unexpected::Str(tag), self.expected));
+        }
+        let fn_apply = FnApply {
+            trait_object,
+            variant: self.visit_str(tag)?,
+            scope: self.scope,
+        };
+        fn_apply.deserialize(UnitDeserializer::new())
+    }
 }
 
 impl<'a, T: ?Sized + 'static> Copy for MapLookupVisitor<'a, T> {}
@@ -17,7 +94,7 @@ impl<'a, T: ?Sized + 'static> Clone for MapLookupVisitor<'a, T> {
 }
 
 impl<'de, 'a, T: ?Sized + 'static> Visitor<'de> for MapLookupVisitor<'a, T> {
-    type Value = DeserializeFn<T>;
+    type Value = Variant<T>;
 
     fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
         Expected::fmt(self.expected, formatter)
@@ -28,39 +105,90 @@ impl<'de, 'a, T: ?Sized + 'static> Visitor<'de> for MapLookupVisitor<'a, T> {
         E: serde::de::Error,
     {
         match self.registry.map.get(key) {
-            Some(Some(value)) => Ok(*value),
+            Some(Some(registration)) => self.allowed(registration),
             Some(None) => Err(de::Error::custom(format_args!(
                 "non-unique tag of {}: {:?}",
                 self.expected, key
             ))),
-            None => Err(de::Error::unknown_variant(key, &self.registry.names)),
+            None => match self.registry.unknown {
+                Some(_) if !self.scope.allows(key) => Err(self.disallowed(key)),
+                Some(unknown_fn) => Ok(Variant::Unknown(
+                    key.to_owned(),
+                    unknown_fn,
+                    self.registry.limits,
+                )),
+                None => Err(de::Error::custom(UnknownTag {
+                    tag: key,
+                    names: &self.registry.names,
+                    suggest: self.registry.suggest,
+                })),
+            },
+        }
+    }
+
+    fn visit_u64<E>(self, id: u64) -> Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        let entry = u32::try_from(id)
+            .ok()
+            .and_then(|id| self.registry.ids.get(&id));
+        match entry {
+            Some(Some(registration)) => self.allowed(registration),
+            Some(None) => Err(de::Error::custom(format_args!(
+                "non-unique id of {}: {}",
+                self.expected, id
+            ))),
+            None => Err(de::Error::invalid_value(Unexpected::Unsigned(id), &self)),
         }
     }
 }
 
 impl<'de, 'a, T: ?Sized + 'static> DeserializeSeed<'de> for MapLookupVisitor<'a, T> {
-    type Value = DeserializeFn<T>;
+    type Value = Variant<T>;
 
     fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
     where
         D: Deserializer<'de>,
     {
-        deserializer.deserialize_str(self)
+        if self.expects_id(deserializer.is_human_readable()) {
+            deserializer.deserialize_u32(self)
+        } else {
+            deserializer.deserialize_str(self)
+        }
     }
 }
 
-pub struct FnApply<T: ?Sized> {
-    pub deserialize_fn: DeserializeFn<T>,
+pub struct FnApply<'a, T: ?Sized + 'static> {
+    pub trait_object: &'static str,
+    pub variant: Variant<T>,
+    pub scope: Scope<'a>,
 }
 
-impl<'de, T: ?Sized> DeserializeSeed<'de> for FnApply<T> {
+impl<'de, 'a, T: ?Sized> DeserializeSeed<'de> for FnApply<'a, T> {
     type Value = Box<T>;
 
     fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
     where
         D: Deserializer<'de>,
     {
-        let mut erased = <dyn erased_serde::Deserializer>::erase(deserializer);
-        (self.deserialize_fn)(&mut erased).map_err(de::Error::custom)
+        match self.variant {
+            Variant::Registered(registration) => {
+                let mut erased = <dyn erased_serde::Deserializer>::erase(deserializer);
+                registration
+                    .deserialize_with(self.scope.context, &mut erased)
+                    .map_err(|err| {
+                        de::Error::custom(format_args!(
+                            "while deserializing `{}` as dyn {}: {}",
+                            registration.name, self.trait_object, err,
+                        ))
+                    })
+            }
+            Variant::Unknown(tag, unknown_fn, limits) => {
+                let budget = Budget::new(self.trait_object, limits);
+                let content = ContentSeed::new(&budget).deserialize(deserializer)?;
+                Ok(unknown_fn(Unknown::new(tag, content.into_owned())))
+            }
+        }
     }
 }
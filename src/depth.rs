@@ -0,0 +1,70 @@
+use crate::content::Limits;
+use serde::de;
+
+// Counts the trait objects being deserialized inside one another on the current
+// thread, so that deeply nested input fails with an error instead of
+// overflowing the stack. Only traits with a recursion limit are counted, and
+// such limits require std for the thread local.
+#[cfg(feature = "std")]
+mod counter {
+    use core::cell::Cell;
+
+    std::thread_local! {
+        static DEPTH: Cell<usize> = const { Cell::new(0) };
+    }
+
+    pub fn increment() -> usize {
+        DEPTH.with(|depth| {
+            let new = depth.get() + 1;
+            depth.set(new);
+            new
+        })
+    }
+
+    pub fn decrement() {
+        DEPTH.with(|depth| depth.set(depth.get() - 1));
+    }
+}
+
+// Without std, the recursion macro refuses to compile any limit.
+#[cfg(not(feature = "std"))]
+mod counter {
+    pub fn increment() -> usize {
+        unreachable!()
+    }
+
+    pub fn decrement() {
+        unreachable!()
+    }
+}
+
+// Held for as long as one trait object of a trait with a recursion limit is
+// being deserialized.
+pub struct Guard {
+    _private: (),
+}
+
+pub fn enter<E>(trait_object: &str, limits: &Limits) -> Result<Option<Guard>, E>
+where
+    E: de::Error,
+{
+    let Some(limit) = limits.recursion else {
+        return Ok(None);
+    };
+    let depth = counter::increment();
+    let guard = Guard { _private: () };
+    if depth > limit {
+        Err(de::Error::custom(format_args!(
+            "dyn {} exceeds the limit of {} nested trait objects",
+            trait_object, limit,
+        )))
+    } else {
+        Ok(Some(guard))
+    }
+}
+
+impl Drop for Guard {
+    fn drop(&mut self) {
+        counter::decrement();
+    }
+}
@@ -0,0 +1,114 @@
+use crate::de::{FnApply, MapLookupVisitor, Scope, Variant};
+use crate::depth;
+use crate::private::Registry;
+use crate::ser::{Id, Wrap};
+use alloc::boxed::Box;
+use core::fmt;
+use serde::de::{DeserializeSeed, Deserializer, EnumAccess, VariantAccess, Visitor};
+use serde::ser::{self, Serializer};
+
+pub fn serialize<S, T>(
+    serializer: S,
+    trait_object: &'static str,
+    variant: &str,
+    static_variant: Option<&'static str>,
+    id: Id,
+    concrete: &T,
+) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+    T: ?Sized + erased_serde::Serialize,
+{
+    let Some(static_variant) = static_variant else {
+        return Err(ser::Error::custom(format_args!(
+            "unknown tag {:?} of dyn {} can not be serialized as an enum variant",
+            variant, trait_object,
+        )));
+    };
+    // Formats that are not human readable identify variants by index, which is
+    // the impl's id, so impls of traits serialized that way need one.
+    let variant_index = match id {
+        Id::Some(id) => id,
+        Id::None | Id::Missing if !serializer.is_human_readable() => {
+            return Err(ser::Error::custom(format_args!(
+                "`{}` has no id, which dyn {} requires in formats that are not human readable",
+                variant, trait_object,
+            )));
+        }
+        Id::None | Id::Missing => 0,
+    };
+    serializer.serialize_newtype_variant(
+        trait_object,
+        variant_index,
+        static_variant,
+        &Wrap(concrete),
+    )
+}
+
+pub fn deserialize<'de, D, T>(
+    deserializer: D,
+    trait_object: &'static str,
+    registry: &'static Registry<T>,
+    scope: Scope,
+) -> Result<Box<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: ?Sized,
+{
+    let _depth = depth::enter(trait_object, &registry.limits)?;
+    let visitor = TaggedVisitor {
+        trait_object,
+        registry,
+        scope,
+    };
+    deserializer.deserialize_enum(trait_object, &registry.names, visitor)
+}
+
+struct TaggedVisitor<'a, T: ?Sized + 'static> {
+    trait_object: &'static str,
+    registry: &'static Registry<T>,
+    scope: Scope<'a>,
+}
+
+impl<'de, 'a, T: ?Sized> Visitor<'de> for TaggedVisitor<'a, T> {
+    type Value = Box<T>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "dyn {}", self.trait_object)
+    }
+
+    fn visit_enum<A>(self, data: A) -> Result<Self::Value, A::Error>
+    where
+        A: EnumAccess<'de>,
+    {
+        let map_lookup = MapLookupVisitor {
+            expected: &self,
+            registry: self.registry,
+            scope: self.scope,
+        };
+        let (variant, access) = data.variant_seed(VariantName(map_lookup))?;
+        access.newtype_variant_seed(FnApply {
+            trait_object: self.trait_object,
+            variant,
+            scope: self.scope,
+        })
+    }
+}
+
+// Variant names are identifiers rather than strings in formats like RON.
+struct VariantName<'a, T: ?Sized + 'static>(MapLookupVisitor<'a, T>);
+
+impl<'de, 'a, T: ?Sized + 'static> DeserializeSeed<'de> for VariantName<'a, T> {
+    type Value = Variant<T>;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        if self.0.expects_id(deserializer.is_human_readable()) {
+            deserializer.deserialize_u32(self.0)
+        } else {
+            deserializer.deserialize_identifier(self.0)
+        }
+    }
+}
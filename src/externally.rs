@@ -1,6 +1,7 @@
-use crate::de::{FnApply, MapLookupVisitor};
+use crate::de::{FnApply, MapLookupVisitor, Scope};
+use crate::depth;
 use crate::private::Registry;
-use crate::ser::Wrap;
+use crate::ser::{Id, VariantTag, Wrap};
 use alloc::boxed::Box;
 use core::fmt;
 use serde::de::{self, Deserializer, MapAccess, Visitor};
@@ -8,15 +9,17 @@ use serde::ser::{SerializeMap, Serializer};
 
 pub fn serialize<S, T>(
     serializer: S,
-    variant: &'static str,
+    variant: &str,
+    id: Id,
     concrete: &T,
 ) -> Result<S::Ok, S::Error>
 where
     S: Serializer,
     T: ?Sized + erased_serde::Serialize,
 {
+    let variant = VariantTag::new(variant, id, serializer.is_human_readable());
     let mut ser = serializer.serialize_map(Some(1))?;
-    ser.serialize_entry(variant, &Wrap(concrete))?;
+    ser.serialize_entry(&variant, &Wrap(concrete))?;
     ser.end()
 }
 
@@ -24,30 +27,50 @@ pub fn deserialize<'de, D, T>(
     deserializer: D,
     trait_object: &'static str,
     registry: &'static Registry<T>,
+    scope: Scope,
 ) -> Result<Box<T>, D::Error>
 where
     D: Deserializer<'de>,
     T: ?Sized,
 {
+    let _depth = depth::enter(trait_object, &registry.limits)?;
     let visitor = TaggedVisitor {
         trait_object,
         registry,
+        scope,
     };
-    deserializer.deserialize_map(visitor)
+    if registry.unit_shorthand && deserializer.is_human_readable() {
+        deserializer.deserialize_any(visitor)
+    } else {
+        deserializer.deserialize_map(visitor)
+    }
 }
 
-struct TaggedVisitor<T: ?Sized + 'static> {
+struct TaggedVisitor<'a, T: ?Sized + 'static> {
     trait_object: &'static str,
     registry: &'static Registry<T>,
+    scope: Scope<'a>,
 }
 
-impl<'de, T: ?Sized> Visitor<'de> for TaggedVisitor<T> {
+impl<'de, 'a, T: ?Sized> Visitor<'de> for TaggedVisitor<'a, T> {
     type Value = Box<T>;
 
     fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
         write!(formatter, "dyn {}", self.trait_object)
     }
 
+    fn visit_str<E>(self, tag: &str) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        let map_lookup = MapLookupVisitor {
+            expected: &self,
+            registry: self.registry,
+            scope: self.scope,
+        };
+        map_lookup.unit_shorthand(self.trait_object, tag)
+    }
+
     fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
     where
         A: MapAccess<'de>,
@@ -55,13 +78,18 @@ impl<'de, T: ?Sized> Visitor<'de> for TaggedVisitor<T> {
         let map_lookup = MapLookupVisitor {
             expected: &self,
             registry: self.registry,
+            scope: self.scope,
         };
-        let Some(deserialize_fn) = map.next_key_seed(map_lookup)? else {
+        let Some(variant) = map.next_key_seed(map_lookup)? else {
             return Err(de::Error::custom(format_args!(
                 "expected externally tagged dyn {}",
                 self.trait_object
             )));
         };
-        map.next_value_seed(FnApply { deserialize_fn })
+        map.next_value_seed(FnApply {
+            trait_object: self.trait_object,
+            variant,
+            scope: self.scope,
+        })
     }
 }
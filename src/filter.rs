@@ -0,0 +1,145 @@
+use crate::de::Scope;
+use crate::private::{Deserializable, Registered};
+use alloc::boxed::Box;
+use core::any;
+use core::fmt::{self, Debug};
+use core::marker::PhantomData;
+use core::ops::{Deref, DerefMut};
+use serde::de::{Deserialize, Deserializer};
+use serde::ser::{Serialize, Serializer};
+
+/// Which tags a [`Filtered`] trait object accepts.
+///
+/// Impls are judged by the name they are registered under, so aliases and ids
+/// in the input are allowed exactly when the name is. For traits with an
+/// `unknown` fallback, unrecognized tags are passed as written.
+pub trait Policy {
+    /// Called with the tag of each trait object about to be deserialized: the
+    /// name its impl is registered under, even if the input gave an alias or
+    /// id, or for an unrecognized tag under an `unknown` fallback, the tag as
+    /// it appears in the input.
+    ///
+    /// Returning false fails deserialization with a "disallowed tag" error
+    /// instead of deserializing the impl or falling back to `unknown`.
+    fn allows(tag: &str) -> bool;
+}
+
+/// A `Box<dyn Trait>` that only deserializes from tags allowed by `P`.
+///
+/// Input from untrusted sources can otherwise name any impl linked into the
+/// program, including ones from dependencies that were never meant to be
+/// reachable from there. Disallowed tags fail with an error even though they
+/// are registered.
+///
+/// The policy applies to this trait object only. Trait objects nested inside
+/// the data of an allowed impl are deserialized unfiltered unless their fields
+/// are `Filtered` too.
+///
+/// ```
+/// # use serde::{Deserialize, Serialize};
+/// #
+/// #[typetag::serde]
+/// trait Action {}
+///
+/// #[derive(Serialize, Deserialize)]
+/// struct Like;
+///
+/// #[typetag::serde]
+/// impl Action for Like {}
+///
+/// #[derive(Serialize, Deserialize)]
+/// struct DeleteAccount;
+///
+/// #[typetag::serde]
+/// impl Action for DeleteAccount {}
+///
+/// struct Public;
+///
+/// impl typetag::Policy for Public {
+///     fn allows(tag: &str) -> bool {
+///         tag == "Like"
+///     }
+/// }
+///
+/// fn main() {
+///     type PublicAction = typetag::Filtered<dyn Action, Public>;
+///
+///     assert!(serde_json::from_str::<PublicAction>(r#"{"Like":null}"#).is_ok());
+///     assert!(serde_json::from_str::<PublicAction>(r#"{"DeleteAccount":null}"#).is_err());
+/// }
+/// ```
+pub struct Filtered<T: ?Sized, P> {
+    value: Box<T>,
+    policy: PhantomData<fn() -> P>,
+}
+
+impl<T: ?Sized, P> Filtered<T, P> {
+    /// Wrap a trait object, for example to serialize it as a `Filtered` field.
+    pub fn new(value: Box<T>) -> Self {
+        Filtered {
+            value,
+            policy: PhantomData,
+        }
+    }
+
+    /// Unwrap the trait object, which has already passed the policy.
+    pub fn into_inner(self) -> Box<T> {
+        self.value
+    }
+}
+
+impl<T: ?Sized, P> Deref for Filtered<T, P> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.value
+    }
+}
+
+impl<T: ?Sized, P> DerefMut for Filtered<T, P> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.value
+    }
+}
+
+impl<T: ?Sized, P> From<Box<T>> for Filtered<T, P> {
+    fn from(value: Box<T>) -> Self {
+        Filtered::new(value)
+    }
+}
+
+impl<T: ?Sized, P> Debug for Filtered<T, P> {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "Filtered<{}>", any::type_name::<T>())
+    }
+}
+
+impl<T, P> Serialize for Filtered<T, P>
+where
+    T: ?Sized + Serialize,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        self.value.serialize(serializer)
+    }
+}
+
+impl<'de, T, P> Deserialize<'de> for Filtered<T, P>
+where
+    T: ?Sized + Deserializable,
+    P: Policy,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let scope = Scope {
+            context: None,
+            policy: Some(P::allows),
+        };
+        let value = <T::Registered as Registered>::deserialize(deserializer, scope)?;
+        Ok(Filtered::new(T::from_strictest(value)))
+    }
+}
@@ -1,7 +1,8 @@
-use crate::content::Content;
-use crate::de::{FnApply, MapLookupVisitor};
+use crate::content::{Budget, Content, ContentSeed};
+use crate::de::{FnApply, MapLookupVisitor, Scope};
+use crate::depth;
 use crate::private::Registry;
-use crate::ser::{InternallyTaggedSerializer, Wrap};
+use crate::ser::{Id, InternallyTaggedSerializer, VariantTag, Wrap};
 use alloc::borrow::ToOwned;
 use alloc::boxed::Box;
 use alloc::string::String;
@@ -17,7 +18,8 @@ use serde::ser::{Serialize, Serializer};
 pub fn serialize<S, T>(
     serializer: S,
     tag: &'static str,
-    variant: &'static str,
+    variant: &str,
+    id: Id,
     concrete: &T,
 ) -> Result<S::Ok, S::Error>
 where
@@ -26,7 +28,7 @@ where
 {
     let adapter = InternallyTaggedSerializer {
         tag,
-        variant,
+        variant: VariantTag::new(variant, id, serializer.is_human_readable()),
         delegate: serializer,
     };
     Wrap(concrete).serialize(adapter)
@@ -38,36 +40,56 @@ pub fn deserialize<'de, D, T>(
     tag: &'static str,
     default_variant: Option<&'static str>,
     registry: &'static Registry<T>,
+    scope: Scope,
 ) -> Result<Box<T>, D::Error>
 where
     D: Deserializer<'de>,
     T: ?Sized,
 {
+    let _depth = depth::enter(trait_object, &registry.limits)?;
     let visitor = TaggedVisitor {
         trait_object,
         tag,
         default_variant,
         registry,
+        scope,
     };
-    deserializer.deserialize_map(visitor)
+    if registry.unit_shorthand && deserializer.is_human_readable() {
+        deserializer.deserialize_any(visitor)
+    } else {
+        deserializer.deserialize_map(visitor)
+    }
 }
 
 pub(crate) const DEFAULT_KEY: &str = "value";
 
-struct TaggedVisitor<T: ?Sized + 'static> {
+struct TaggedVisitor<'a, T: ?Sized + 'static> {
     trait_object: &'static str,
     tag: &'static str,
     default_variant: Option<&'static str>,
     registry: &'static Registry<T>,
+    scope: Scope<'a>,
 }
 
-impl<'de, T: ?Sized> Visitor<'de> for TaggedVisitor<T> {
+impl<'de, 'a, T: ?Sized> Visitor<'de> for TaggedVisitor<'a, T> {
     type Value = Box<T>;
 
     fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
         write!(formatter, "dyn {}", self.trait_object)
     }
 
+    fn visit_str<E>(self, tag: &str) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        let map_lookup = MapLookupVisitor {
+            expected: &self,
+            registry: self.registry,
+            scope: self.scope,
+        };
+        map_lookup.unit_shorthand(self.trait_object, tag)
+    }
+
     fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
     where
         A: MapAccess<'de>,
@@ -80,9 +102,16 @@ impl<'de, T: ?Sized> Visitor<'de> for TaggedVisitor<T> {
         let map_lookup = MapLookupVisitor {
             expected: &self,
             registry: self.registry,
+            scope: self.scope,
         };
 
-        let mut deserialize_fn = None;
+        // Fields preceding the tag are buffered, as one level of nesting, with
+        // their keys counted the same as keys of a buffered map. Nothing is
+        // charged if the tag comes first.
+        let budget = Budget::new(self.trait_object, self.registry.limits);
+        let field_seed = || ContentSeed::new(&budget).nested();
+
+        let mut variant = None;
         let mut entries = Vec::new();
 
         while let Some(key) = map.next_key_seed(key_visitor)? {
@@ -91,36 +120,44 @@ impl<'de, T: ?Sized> Visitor<'de> for TaggedVisitor<T> {
                     let value = map.next_value_seed(map_lookup)?;
                     if entries.is_empty() {
                         let fn_apply = FnApply {
-                            deserialize_fn: value,
+                            trait_object: self.trait_object,
+                            variant: value,
+                            scope: self.scope,
                         };
                         let rest = MapWithStringKeys { map };
                         return fn_apply.deserialize(rest);
                     }
-                    deserialize_fn = Some(value);
+                    variant = Some(value);
                     while let Some(key) = map.next_key::<String>()? {
+                        budget.charge_key(key.len())?;
                         let key = Content::String(key);
-                        let value = map.next_value::<Content>()?;
+                        let value = map.next_value_seed(field_seed()?)?;
                         entries.push((key, value));
                     }
                     break;
                 }
                 Key::Other(key) => {
+                    budget.charge_key(key.len())?;
                     let key = Content::String(key);
-                    let value = map.next_value::<Content>()?;
+                    let value = map.next_value_seed(field_seed()?)?;
                     entries.push((key, value));
                 }
             }
         }
 
-        let deserialize_fn = match deserialize_fn {
-            Some(deserialize_fn) => deserialize_fn,
+        let variant = match variant {
+            Some(variant) => variant,
             None => match self.default_variant {
                 Some(variant) => map_lookup.visit_str(variant)?,
                 None => return Err(de::Error::missing_field(self.tag)),
             },
         };
 
-        let fn_apply = FnApply { deserialize_fn };
+        let fn_apply = FnApply {
+            trait_object: self.trait_object,
+            variant,
+            scope: self.scope,
+        };
         let content = Content::Map(entries).into_deserializer();
         fn_apply.deserialize(content)
     }
@@ -232,6 +232,143 @@
 //! {"type":"mouse_button_down","x":10,"y":10}
 //! ```
 //!
+//! Previous names can be kept working after a rename by listing them as
+//! aliases, as in `#[typetag::serde(name = "mouse_button_down", alias =
+//! "Click")]`. Aliases are accepted when deserializing but never produced when
+//! serializing.
+//!
+//! In binary formats, where the tag's string is often bigger than the value, an
+//! impl can also declare a stable numeric id, as in `#[typetag::serde(name =
+//! "Click", id = 7)]`. Formats that are not human readable then write the id in
+//! place of the name. Once any impl of a trait has an id, those formats expect
+//! an id on every impl and fail to serialize an impl without one, so give one to
+//! all of them. Human-readable formats like JSON keep using names.
+//!
+//! Two impls can end up registering the same tag, as when two dependencies both
+//! register `"Gzip"` for `dyn Codec`. Such a tag is normally rejected as
+//! non-unique, but `#[typetag::serde(name = "Gzip", priority = 10)]` claims it
+//! for that impl over any of lower priority. Impls tied at the highest priority
+//! still leave it non-unique, as reported by [`typetag::validate`][validate].
+//!
+//! Default tags can follow one of Serde's case conventions instead of the type
+//! name's own by putting `rename_all = "snake_case"` (or `"kebab-case"`,
+//! `"camelCase"`, etc) in the attribute on the trait.
+//!
+//! Types with the same name in different modules, like `config::Source` and
+//! `net::Source`, would get the same default tag. With `namespace = "module"`
+//! on the trait, default tags are qualified with the impl's `module_path!()`,
+//! as in `"my_crate::net::Source"`, and with `namespace = "crate"` with just the
+//! name of its crate, as in `"my_crate::Source"`. Explicit names are left as
+//! written.
+//!
+//! Data that carries no tag at all can be handled with
+//! `#[typetag::serde(untagged)]`, which like Serde's untagged enums tries every
+//! impl in turn and keeps the first one that deserializes. Impls are tried in
+//! order of their `priority`, highest first, then by name. This is the same
+//! priority that settles shared tags, so an impl given a higher priority to
+//! claim a shared tag is also tried earlier, and the other way around.
+//!
+//! Formats with their own notation for enums, like YAML's `!Click` tags or
+//! RON's `Click(...)`, can be given trait objects as genuine enum variants with
+//! `#[typetag::serde(repr = "enum")]`. Formats that identify variants by index
+//! instead of by name see each impl's `id`, so every impl needs one to be
+//! serialized in formats that are not human readable.
+//!
+//! With `#[typetag::serde(unit_shorthand)]`, human readable input may give just
+//! the tag, as in `"PageLoad"`, for an impl that carries no data. Output keeps
+//! the full tagged form.
+//!
+//! Errors for unrecognized tags propose the closest registered names, as in
+//! ``unknown variant `Clik`, did you mean `Click`?``, and otherwise list at most
+//! sixteen of the expected names. Traits declared with
+//! `#[typetag::serde(no_suggestions)]` skip the proposals.
+//!
+//! Input from untrusted sources can be limited to some of the registered impls
+//! by deserializing a [`typetag::Filtered<dyn Trait, Policy>`][Filtered] in
+//! place of a `Box<dyn Trait>`.
+//!
+//! Internally tagged, adjacently tagged and untagged trait objects buffer their
+//! data in memory when it comes before the tag, as does serde for enums. To
+//! bound what hostile input can make them buffer, declare the trait with
+//! `limits(bytes = 65536, elements = 1024, depth = 16)`, giving any of the
+//! three. The same limits apply to the data of unknown tags kept by an
+//! `unknown` fallback. Input exceeding a limit fails to deserialize.
+//!
+//! Traits whose impls hold `Box<dyn Trait>` of their own, like expression trees,
+//! recurse through several stack frames per level of nesting and can overflow
+//! the stack on deeply nested input before the format's own recursion limit
+//! is reached. `limits(recursion = 64)` makes deserialization fail instead once
+//! trait objects are nested more than 64 deep, counting trait objects of every
+//! trait with a `recursion` limit that are being deserialized inside one
+//! another on the current thread. The count is kept in a thread local, so
+//! recursion limits require typetag's default `std` feature.
+//!
+//! Generic traits are deserializable at the instantiations listed on the trait,
+//! as in `#[typetag::serde(instantiate(Handler<Ping>, Handler<Pong>))]`. Each
+//! of `Box<dyn Handler<Ping>>` and `Box<dyn Handler<Pong>>` then has a registry
+//! of its own, which an impl joins by implementing that instantiation.
+//!
+//! Traits with associated types work the same way, listing the values of the
+//! associated types to support, as in `instantiate(Stage<Output = Frame>)`.
+//! Each impl registers into the instantiation matching its own `type Output`.
+//! Associated types bounded by `where Self: Sized` are not part of the trait
+//! object type and need no instantiation.
+//!
+//! A generic impl needs a tag for each of its instantiations, listed on the
+//! impl as in `#[typetag::serde(instances(Wrapper<u8> = "WrapperU8",
+//! Wrapper<String> = "WrapperStr"))]`. Each listed instance serializes under
+//! its own tag and is registered separately. The attribute also narrows the
+//! impl to exactly those instances: written on `impl<T: Codec> Transform for
+//! Wrapper<T>`, it leaves `Wrapper<u16>` not implementing `Transform` at all,
+//! even if `u16` implements `Codec`. An instance is given a numeric id by
+//! listing it next to the tag, as in `Wrapper<u8> = ("WrapperU8", id = 1)`.
+//!
+//! Impls normally register themselves through the [`inventory`] crate, which
+//! relies on the linker running their constructors at startup. Where that does
+//! not work, as in static libraries whose unreferenced objects are dropped or
+//! on embedded targets, a trait can be declared with `registry = "manual"` and
+//! its impls listed in `typetag::register!` instead. Impls of such a trait
+//! carry the same argument so that they do not submit themselves to inventory,
+//! and an impl whose argument does not match its trait's fails to compile.
+//!
+//! ```
+//! # use serde::{Deserialize, Serialize};
+//! #
+//! #[typetag::serde(registry = "manual")]
+//! trait Shape {
+//!     fn area(&self) -> f64;
+//! }
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Square {
+//!     side: f64,
+//! }
+//!
+//! #[typetag::serde(registry = "manual")]
+//! impl Shape for Square {
+//!     fn area(&self) -> f64 {
+//!         self.side * self.side
+//!     }
+//! }
+//!
+//! typetag::register!(dyn Shape => [Square]);
+//! #
+//! # fn main() {
+//! #     let shape: Box<dyn Shape> = serde_json::from_str(r#"{"Square":{"side":2.0}}"#).unwrap();
+//! #     assert_eq!(shape.area(), 4.0);
+//! # }
+//! ```
+//!
+//! Crates that reach typetag only through a re-export from another crate can
+//! point the generated code at it with `crate = "facade::typetag"`, in the
+//! attribute on the trait, on each impl, and in `typetag::register!`, just like
+//! `#[serde(crate = "...")]`.
+//!
+//! A `Box<dyn Trait>` field can be given a different tag representation than
+//! the trait's with one of `Externally`, `Internally` or `Adjacently` in
+//! [`typetag::with`][with], as in
+//! `#[serde(with = "typetag::with::Internally::<Kind>")]`.
+//!
 //! Conceptually all you're getting with this crate is that we build for you an
 //! enum in which every impl of the trait in your program is automatically
 //! registered as an enum variant. The behavior is the same as if you had
@@ -277,6 +414,15 @@
 //!   anything else supported by Serde. The Serialize and Deserialize impls may
 //!   be derived or handwritten.
 //!
+//! - *Can I deserialize `Arc<dyn Trait>` or `Rc<dyn Trait>`?* **Yes**
+//!
+//!   With typetag's `rc` feature enabled, anywhere `Box<dyn Trait>` is
+//!   deserializable, so are `Arc` and `Rc` of the same trait object type,
+//!   including its `Send` and `Sync` variants. Each one deserializes a separate
+//!   allocation; sharing is not preserved. The feature turns on Serde's own
+//!   `rc` feature, which does the same for `Arc` and `Rc` of every type, so it
+//!   is off by default.
+//!
 //! - *Even in WebAssembly?* **Yes**
 //!
 //!   Wasm is supported but with some extra setup described [here][wasm].
@@ -315,19 +461,37 @@
 #![allow(unknown_lints, mismatched_lifetime_syntaxes)]
 
 extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
 
 mod adjacently;
 mod content;
 mod de;
+mod depth;
+mod enumerated;
 mod externally;
+mod filter;
 mod internally;
 mod is_serialize_str;
+mod namespace;
 mod private;
+mod registry;
+mod seed;
 mod ser;
+mod suggest;
+mod unknown;
+mod untagged;
+mod validate;
+pub mod with;
 
 include!(concat!(env!("OUT_DIR"), "/private.rs"));
 
-pub use typetag_impl::{deserialize, serde, serialize};
+pub use crate::filter::{Filtered, Policy};
+pub use crate::registry::{registry, Tag, Tags};
+pub use crate::seed::{seed, DeserializeWithContext, Seed};
+pub use crate::unknown::Unknown;
+pub use crate::validate::{validate, Collision, Collisions, Origin};
+pub use typetag_impl::{deserialize, register, serde, serialize};
 
 // Object-safe trait bound inserted by typetag serialization. We want this just
 // so the serialization requirement appears on rustdoc's view of your trait.
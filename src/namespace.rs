@@ -0,0 +1,74 @@
+// Default tag names qualified with the name of the crate containing the impl,
+// for traits declared with namespace = "crate". The impl passes its bare name
+// in each case convention, and all eight qualified names are laid out one
+// after another in a single buffer at compile time, because const evaluation
+// cannot produce a string by concatenation.
+pub struct CrateQualified<const N: usize> {
+    bytes: [u8; N],
+    ends: [usize; 8],
+}
+
+// Size of the buffer needed by CrateQualified::new for the same arguments.
+pub const fn crate_qualified_len(module_path: &str, names: [&str; 8]) -> usize {
+    let prefix = crate_name(module_path).len() + 2;
+    let mut len = 0;
+    let mut i = 0;
+    while i < names.len() {
+        len += prefix + names[i].len();
+        i += 1;
+    }
+    len
+}
+
+impl<const N: usize> CrateQualified<N> {
+    pub const fn new(module_path: &str, names: [&str; 8]) -> Self {
+        let crate_name = crate_name(module_path).as_bytes();
+        let mut bytes = [0; N];
+        let mut ends = [0; 8];
+        let mut len = 0;
+        let mut i = 0;
+        while i < names.len() {
+            let pieces = [crate_name, b"::", names[i].as_bytes()];
+            let mut j = 0;
+            while j < pieces.len() {
+                let mut k = 0;
+                while k < pieces[j].len() {
+                    bytes[len] = pieces[j][k];
+                    len += 1;
+                    k += 1;
+                }
+                j += 1;
+            }
+            ends[i] = len;
+            i += 1;
+        }
+        CrateQualified { bytes, ends }
+    }
+
+    pub const fn get(&'static self, i: usize) -> &'static str {
+        let start = if i == 0 { 0 } else { self.ends[i - 1] };
+        let (bytes, _rest) = self.bytes.split_at(self.ends[i]);
+        let (_previous, bytes) = bytes.split_at(start);
+        match core::str::from_utf8(bytes) {
+            Ok(name) => name,
+            Err(_) => panic!("crate-qualified name is not UTF-8"),
+        }
+    }
+}
+
+// The first segment of module_path!().
+const fn crate_name(module_path: &str) -> &str {
+    let bytes = module_path.as_bytes();
+    let mut i = 0;
+    while i + 1 < bytes.len() {
+        if bytes[i] == b':' && bytes[i + 1] == b':' {
+            let (crate_name, _rest) = bytes.split_at(i);
+            return match core::str::from_utf8(crate_name) {
+                Ok(crate_name) => crate_name,
+                Err(_) => panic!("module path is not UTF-8"),
+            };
+        }
+        i += 1;
+    }
+    module_path
+}
@@ -7,6 +7,29 @@ pub extern crate once_cell;
 #[doc(hidden)]
 pub extern crate serde;
 
+// Recursion limits count nesting in a thread local, so they are only
+// available with std.
+#[cfg(feature = "std")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __typetag_recursion {
+    ($limit:expr) => {
+        ::core::option::Option::Some($limit)
+    };
+}
+
+#[cfg(not(feature = "std"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __typetag_recursion {
+    ($limit:expr) => {
+        ::core::compile_error!("limits(recursion = ...) requires typetag's std feature")
+    };
+}
+
+#[doc(hidden)]
+pub use crate::__typetag_recursion as recursion;
+
 #[doc(hidden)]
 pub mod externally {
     #[doc(hidden)]
@@ -25,12 +48,44 @@ pub mod adjacently {
     pub use crate::adjacently::*;
 }
 
+#[doc(hidden)]
+pub mod enumerated {
+    #[doc(hidden)]
+    pub use crate::enumerated::*;
+}
+
+#[doc(hidden)]
+pub mod untagged {
+    #[doc(hidden)]
+    pub use crate::untagged::*;
+}
+
+#[doc(hidden)]
+pub use crate::content::Limits;
+#[doc(hidden)]
+pub use crate::de::Scope;
+#[doc(hidden)]
+pub use crate::namespace::{crate_qualified_len, CrateQualified};
+#[doc(hidden)]
+pub use crate::seed::{downcast_context, missing_context, DeserializeWithContext};
+#[doc(hidden)]
+pub use crate::ser::Id;
 #[doc(hidden)]
 pub use alloc::collections::btree_map;
 #[doc(hidden)]
+pub use core::any::type_name;
+#[doc(hidden)]
+pub use core::any::Any;
+#[doc(hidden)]
+pub use core::convert::{AsRef, From};
+#[doc(hidden)]
+pub use core::default::Default;
+#[doc(hidden)]
 pub use core::option::Option;
 #[doc(hidden)]
 pub use core::result::Result;
+#[doc(hidden)]
+pub use core::{concat, file, line, module_path};
 
 #[doc(hidden)]
 pub type Box<T> = alloc::boxed::Box<T>;
@@ -45,14 +100,221 @@ pub type Vec<T> = alloc::vec::Vec<T>;
 pub type DeserializeFn<T> = fn(&mut dyn erased_serde::Deserializer) -> erased_serde::Result<Box<T>>;
 
 #[doc(hidden)]
-pub struct Registry<T: ?Sized> {
+pub type SeededFn<T> =
+    fn(&dyn Any, &mut dyn erased_serde::Deserializer) -> erased_serde::Result<Box<T>>;
+
+#[doc(hidden)]
+pub struct Registration<T: ?Sized> {
+    #[doc(hidden)]
+    pub name: &'static str,
+    #[doc(hidden)]
+    pub aliases: &'static [&'static str],
+    #[doc(hidden)]
+    pub id: Option<u32>,
+    #[doc(hidden)]
+    pub priority: i32,
+    #[doc(hidden)]
+    pub deserialize: DeserializeFn<T>,
+    // Present for impls deserialized through DeserializeWithContext.
+    #[doc(hidden)]
+    pub seeded: Option<SeededFn<T>>,
+    #[doc(hidden)]
+    pub origin: crate::Origin,
+}
+
+impl<T: ?Sized> Registration<T> {
+    pub(crate) fn deserialize_with(
+        &self,
+        context: Option<&dyn Any>,
+        deserializer: &mut dyn erased_serde::Deserializer,
+    ) -> erased_serde::Result<Box<T>> {
+        match (context, self.seeded) {
+            (Some(context), Some(seeded)) => seeded(context, deserializer),
+            _ => (self.deserialize)(deserializer),
+        }
+    }
+
+    // The name and aliases accepted for this impl, each once, so that an alias
+    // repeating the name or another alias does not collide with itself.
+    pub(crate) fn tags(&self) -> Vec<&'static str> {
+        let mut tags: Vec<&'static str> = core::iter::once(self.name)
+            .chain(self.aliases.iter().copied())
+            .collect();
+        tags.sort_unstable();
+        tags.dedup();
+        tags
+    }
+}
+
+#[doc(hidden)]
+pub type UnknownFn<T> = fn(crate::Unknown) -> Box<T>;
+
+#[doc(hidden)]
+pub struct Registry<T: ?Sized + 'static> {
     #[doc(hidden)]
-    pub map: BTreeMap<&'static str, Option<DeserializeFn<T>>>,
+    pub map: BTreeMap<&'static str, Option<&'static Registration<T>>>,
+    #[doc(hidden)]
+    pub ids: BTreeMap<u32, Option<&'static Registration<T>>>,
     #[doc(hidden)]
     pub names: Vec<&'static str>,
+    #[doc(hidden)]
+    pub unknown: Option<UnknownFn<T>>,
+    // Whether errors for unknown tags propose similar registered names.
+    #[doc(hidden)]
+    pub suggest: bool,
+    // Whether a bare tag is accepted for an impl that carries no data.
+    #[doc(hidden)]
+    pub unit_shorthand: bool,
+    #[doc(hidden)]
+    pub limits: Limits,
+    // Every registration, including colliding ones, for typetag::validate and
+    // in the order untagged trait objects try them.
+    #[doc(hidden)]
+    pub registrations: Vec<&'static Registration<T>>,
+}
+
+impl<T: ?Sized + 'static> Registry<T> {
+    #[doc(hidden)]
+    pub fn new<I>(
+        registrations: I,
+        unknown: Option<UnknownFn<T>>,
+        suggest: bool,
+        unit_shorthand: bool,
+        limits: Limits,
+    ) -> Self
+    where
+        I: IntoIterator<Item = &'static Registration<T>>,
+    {
+        let mut map = BTreeMap::new();
+        let mut ids = BTreeMap::new();
+        let mut names = Vec::new();
+        let mut registrations: Vec<_> = registrations.into_iter().collect();
+        registrations.sort_by(|a, b| b.priority.cmp(&a.priority).then(a.name.cmp(b.name)));
+        for &registration in &registrations {
+            // Aliases are accepted on input exactly like the name, so they
+            // compete for uniqueness too, but are not listed as variants.
+            for tag in registration.tags() {
+                insert_unique(map.entry(tag), registration);
+            }
+            if let Some(id) = registration.id {
+                insert_unique(ids.entry(id), registration);
+            }
+            names.push(registration.name);
+        }
+        names.sort_unstable();
+        names.dedup();
+        Registry {
+            map,
+            ids,
+            names,
+            unknown,
+            suggest,
+            unit_shorthand,
+            limits,
+            registrations,
+        }
+    }
+}
+
+// Registrations arrive in order of descending priority, so one already in the
+// entry takes precedence unless it has the same priority as the newcomer, in
+// which case the tag belongs to neither.
+fn insert_unique<K, T>(
+    entry: BTreeMapEntry<K, Option<&'static Registration<T>>>,
+    registration: &'static Registration<T>,
+) where
+    K: Ord,
+    T: ?Sized,
+{
+    match entry {
+        BTreeMapEntry::Vacant(entry) => {
+            entry.insert(Some(registration));
+        }
+        BTreeMapEntry::Occupied(mut entry) => match entry.get() {
+            Some(existing) if existing.priority > registration.priority => {}
+            _ => {
+                entry.insert(None);
+            }
+        },
+    }
 }
 
 #[doc(hidden)]
 pub trait Strictest {
-    type Object: ?Sized;
+    type Object: ?Sized + 'static;
 }
+
+#[doc(hidden)]
+pub trait Registered: Strictest {
+    fn registry() -> &'static Registry<Self::Object>;
+
+    fn deserialize<'de, D>(deserializer: D, scope: Scope) -> Result<Box<Self::Object>, D::Error>
+    where
+        D: serde::Deserializer<'de>;
+}
+
+// Implemented for every typetag trait object type, so that typetag::with can
+// serialize a Box<dyn Trait> without knowing the trait.
+#[doc(hidden)]
+pub trait Serializable: erased_serde::Serialize {
+    const NAME: &'static str;
+
+    fn typetag_name(&self) -> &str;
+
+    fn typetag_id(&self) -> Id;
+}
+
+// Implemented for dyn Trait with each combination of marker traits that can be
+// deserialized, pointing back to the trait object type holding the registry.
+#[doc(hidden)]
+pub trait Deserializable {
+    type Registered: ?Sized + Registered;
+
+    const NAME: &'static str;
+
+    fn from_strictest(object: Box<<Self::Registered as Strictest>::Object>) -> Box<Self>;
+}
+
+// Implemented by the typetag attribute on an impl block, once per trait object
+// type that the impl is tagged for.
+#[doc(hidden)]
+pub trait Tagged<O: ?Sized> {
+    const TAG: &'static str;
+    const ALIASES: &'static [&'static str] = &[];
+    const ID: Option<u32> = None;
+    const PRIORITY: i32 = 0;
+
+    // Whether other impls registered for the same trait object have ids,
+    // overridden for impls registered for deserialization.
+    fn registry_has_ids() -> bool {
+        false
+    }
+}
+
+// Implemented alongside Tagged when the impl is registered for deserialization.
+#[doc(hidden)]
+pub trait TaggedDeserialize<O: ?Sized + Strictest>: Tagged<O> {
+    const REGISTRATION: Registration<O::Object>;
+}
+
+// Implemented by typetag::register! for traits declared with
+// #[typetag::serde(registry = "manual")].
+#[doc(hidden)]
+pub trait ManualRegistry: Strictest {
+    const REGISTRATIONS: &'static [Registration<Self::Object>];
+}
+
+// Implemented for dyn Trait according to the registry argument of the trait,
+// which impls must match: a manual impl of an inventory trait would never be
+// registered, and an inventory impl of a manual trait has nowhere to submit to.
+#[doc(hidden)]
+pub trait RegistryInventory {}
+
+#[doc(hidden)]
+pub trait RegistryManual {}
+
+#[doc(hidden)]
+pub fn assert_registry_inventory<T: ?Sized + RegistryInventory>() {}
+
+#[doc(hidden)]
+pub fn assert_registry_manual<T: ?Sized + RegistryManual>() {}
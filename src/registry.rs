@@ -0,0 +1,106 @@
+use crate::private::Registered;
+use alloc::vec::{self, Vec};
+use core::iter::FusedIterator;
+
+/// Iterate the tags registered for deserializing a typetag trait object.
+///
+/// Tags are produced in sorted order, each one once, regardless of how many
+/// impls registered it. A tag claimed by more than one impl at the same
+/// `priority = N` is reported as [duplicate][Tag::is_duplicate]; trait objects
+/// using that tag can not be deserialized. A tag claimed at different
+/// priorities belongs to the highest and is not a duplicate. Aliases are not
+/// listed, but an alias that collides with another impl's tag makes that tag a
+/// duplicate. To find which impls collide, use
+/// [`typetag::validate`][crate::validate].
+///
+/// ```
+/// # use serde::{Deserialize, Serialize};
+/// #
+/// #[typetag::serde]
+/// trait WebEvent {}
+///
+/// #[derive(Serialize, Deserialize)]
+/// struct PageLoad;
+///
+/// #[typetag::serde]
+/// impl WebEvent for PageLoad {}
+///
+/// #[derive(Serialize, Deserialize)]
+/// struct Click;
+///
+/// #[typetag::serde]
+/// impl WebEvent for Click {}
+///
+/// fn main() {
+///     let names: Vec<&str> = typetag::registry::<dyn WebEvent>()
+///         .map(|tag| tag.name())
+///         .collect();
+///     assert_eq!(names, ["Click", "PageLoad"]);
+/// }
+/// ```
+pub fn registry<T>() -> Tags
+where
+    T: ?Sized + Registered,
+{
+    let registry = T::registry();
+    let tags: Vec<Tag> = registry
+        .names
+        .iter()
+        .map(|name| Tag {
+            name,
+            duplicate: registry.map[name].is_none(),
+        })
+        .collect();
+    Tags {
+        iter: tags.into_iter(),
+    }
+}
+
+/// Iterator over the tags registered for a trait object, returned by
+/// [`typetag::registry`][registry].
+#[derive(Debug)]
+pub struct Tags {
+    iter: vec::IntoIter<Tag>,
+}
+
+/// One tag registered for a trait object.
+#[derive(Copy, Clone, Debug)]
+pub struct Tag {
+    name: &'static str,
+    duplicate: bool,
+}
+
+impl Tag {
+    /// The string that identifies this impl in serialized data.
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Whether more than one impl registered this same tag at its highest
+    /// priority, leaving it unable to be deserialized.
+    pub fn is_duplicate(&self) -> bool {
+        self.duplicate
+    }
+}
+
+impl Iterator for Tags {
+    type Item = Tag;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl DoubleEndedIterator for Tags {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.iter.next_back()
+    }
+}
+
+impl ExactSizeIterator for Tags {}
+
+impl FusedIterator for Tags {}
@@ -0,0 +1,155 @@
+use crate::de::Scope;
+use crate::private::Registered;
+use alloc::boxed::Box;
+use core::any::{self, Any};
+use core::fmt;
+use core::marker::PhantomData;
+use serde::de::{self, DeserializeSeed, Deserializer};
+
+/// Deserialization of a trait impl that needs access to state supplied by the
+/// caller, such as an interner or asset cache.
+///
+/// Impls opt in with `#[typetag::serde(context = Ctx)]` and are then
+/// deserialized through this trait instead of `serde::Deserialize`. The
+/// context is provided by deserializing the trait object with
+/// [`typetag::seed`][seed].
+pub trait DeserializeWithContext<Ctx>: Sized {
+    /// Deserialize this value with access to the caller's `context`.
+    fn deserialize_with_context<'de, D>(context: &Ctx, deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>;
+}
+
+/// Deserialize a `Box<dyn Trait>` with access to a caller-provided context.
+///
+/// Impls registered with `#[typetag::serde(context = Ctx)]` receive `context`
+/// through [`DeserializeWithContext`]. All other impls are deserialized as
+/// usual, ignoring the context.
+///
+/// ```
+/// use serde::de::{Deserialize, DeserializeSeed, Deserializer};
+/// use serde::{Serialize, Serializer};
+/// use std::cell::RefCell;
+/// use typetag::DeserializeWithContext;
+///
+/// #[typetag::serde]
+/// trait Asset {
+///     fn id(&self) -> usize;
+/// }
+///
+/// struct Texture {
+///     id: usize,
+/// }
+///
+/// impl Serialize for Texture {
+///     fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+///         serializer.serialize_u64(self.id as u64)
+///     }
+/// }
+///
+/// #[derive(Default)]
+/// struct Cache {
+///     paths: RefCell<Vec<String>>,
+/// }
+///
+/// impl DeserializeWithContext<Cache> for Texture {
+///     fn deserialize_with_context<'de, D>(cache: &Cache, deserializer: D) -> Result<Self, D::Error>
+///     where
+///         D: Deserializer<'de>,
+///     {
+///         let path = String::deserialize(deserializer)?;
+///         let mut paths = cache.paths.borrow_mut();
+///         paths.push(path);
+///         Ok(Texture { id: paths.len() - 1 })
+///     }
+/// }
+///
+/// #[typetag::serde(context = Cache)]
+/// impl Asset for Texture {
+///     fn id(&self) -> usize {
+///         self.id
+///     }
+/// }
+///
+/// fn main() -> serde_json::Result<()> {
+///     let cache = Cache::default();
+///     let json = r#"{"Texture":"grass.png"}"#;
+///     let mut de = serde_json::Deserializer::from_str(json);
+///     let asset = typetag::seed::<dyn Asset, Cache>(&cache).deserialize(&mut de)?;
+///     assert_eq!(asset.id(), 0);
+///     assert_eq!(*cache.paths.borrow(), ["grass.png"]);
+///     Ok(())
+/// }
+/// ```
+pub fn seed<T, Ctx>(context: &Ctx) -> Seed<T, Ctx>
+where
+    T: ?Sized + Registered,
+    Ctx: Any,
+{
+    Seed {
+        context,
+        object: PhantomData,
+    }
+}
+
+/// [`DeserializeSeed`] for a trait object, returned by
+/// [`typetag::seed`][seed].
+pub struct Seed<'a, T: ?Sized, Ctx> {
+    context: &'a Ctx,
+    object: PhantomData<fn() -> Box<T>>,
+}
+
+impl<'a, T: ?Sized, Ctx> Clone for Seed<'a, T, Ctx> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, T: ?Sized, Ctx> Copy for Seed<'a, T, Ctx> {}
+
+impl<'a, T: ?Sized, Ctx> fmt::Debug for Seed<'a, T, Ctx> {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "Seed<{}>", any::type_name::<T>())
+    }
+}
+
+impl<'de, 'a, T, Ctx> DeserializeSeed<'de> for Seed<'a, T, Ctx>
+where
+    T: ?Sized + Registered,
+    Ctx: Any,
+{
+    type Value = Box<T::Object>;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let scope = Scope {
+            context: Some(self.context),
+            policy: None,
+        };
+        T::deserialize(deserializer, scope)
+    }
+}
+
+// Used by impls registered with a context when deserialized without one.
+#[doc(hidden)]
+pub fn missing_context<T, Ctx>() -> erased_serde::Result<T> {
+    Err(de::Error::custom(format_args!(
+        "can only be deserialized with a context of type `{}`, use typetag::seed",
+        any::type_name::<Ctx>(),
+    )))
+}
+
+#[doc(hidden)]
+pub fn downcast_context<Ctx>(context: &dyn Any) -> erased_serde::Result<&Ctx>
+where
+    Ctx: Any,
+{
+    context.downcast_ref().ok_or_else(|| {
+        de::Error::custom(format_args!(
+            "expected a context of type `{}`",
+            any::type_name::<Ctx>(),
+        ))
+    })
+}
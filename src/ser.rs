@@ -25,13 +25,67 @@ where
     }
 }
 
-pub struct InternallyTaggedSerializer<S> {
+// The numeric tag of an impl, as reported by typetag_id.
+#[derive(Copy, Clone)]
+pub enum Id {
+    Some(u32),
+    None,
+    // The impl has no id but others registered for the same trait object do,
+    // so formats that are not human readable expect an id that it cannot give.
+    Missing,
+}
+
+impl Id {
+    pub fn new(id: Option<u32>, registry_has_ids: impl FnOnce() -> bool) -> Self {
+        match id {
+            Some(id) => Id::Some(id),
+            None if registry_has_ids() => Id::Missing,
+            None => Id::None,
+        }
+    }
+}
+
+// The value written for the tag: the impl's numeric id in formats that are not
+// human readable, if it has one, otherwise its name.
+#[derive(Copy, Clone)]
+pub struct VariantTag<'a> {
+    pub name: &'a str,
+    pub id: Id,
+}
+
+impl<'a> VariantTag<'a> {
+    pub fn new(name: &'a str, id: Id, is_human_readable: bool) -> Self {
+        VariantTag {
+            name,
+            id: if is_human_readable { Id::None } else { id },
+        }
+    }
+}
+
+impl<'a> Serialize for VariantTag<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self.id {
+            Id::Some(id) => serializer.serialize_u32(id),
+            Id::None => serializer.serialize_str(self.name),
+            Id::Missing => Err(ser::Error::custom(format_args!(
+                "`{}` has no id, which formats that are not human readable require \
+                 once any impl of the same trait has one",
+                self.name,
+            ))),
+        }
+    }
+}
+
+pub struct InternallyTaggedSerializer<'a, S> {
     pub tag: &'static str,
-    pub variant: &'static str,
+    pub variant: VariantTag<'a>,
     pub delegate: S,
 }
 
-impl<S> InternallyTaggedSerializer<S>
+impl<'a, S> InternallyTaggedSerializer<'a, S>
 where
     S: Serializer,
 {
@@ -40,13 +94,13 @@ where
         T: ?Sized + Serialize,
     {
         let mut map = self.delegate.serialize_map(Some(2))?;
-        map.serialize_entry(self.tag, self.variant)?;
+        map.serialize_entry(self.tag, &self.variant)?;
         map.serialize_entry(DEFAULT_KEY, value)?;
         map.end()
     }
 }
 
-impl<S> Serializer for InternallyTaggedSerializer<S>
+impl<'a, S> Serializer for InternallyTaggedSerializer<'a, S>
 where
     S: Serializer,
 {
@@ -58,7 +112,7 @@ where
     type SerializeTupleStruct = SerializeTupleStructAsMapValue<S::SerializeMap>;
     type SerializeTupleVariant = SerializeTupleStructAsMapValue<S::SerializeMap>;
     type SerializeMap = S::SerializeMap;
-    type SerializeStruct = SerializeStructAsMap<S::SerializeMap>;
+    type SerializeStruct = SerializeStructAsMap<'a, S::SerializeMap>;
     type SerializeStructVariant = SerializeStructVariantAsMapValue<S::SerializeMap>;
 
     fn serialize_bool(self, value: bool) -> Result<Self::Ok, Self::Error> {
@@ -138,7 +192,7 @@ where
 
     fn serialize_unit(self) -> Result<Self::Ok, Self::Error> {
         let mut map = self.delegate.serialize_map(Some(1))?;
-        map.serialize_entry(self.tag, self.variant)?;
+        map.serialize_entry(self.tag, &self.variant)?;
         map.end()
     }
 
@@ -153,7 +207,7 @@ where
         inner_variant: &'static str,
     ) -> Result<Self::Ok, Self::Error> {
         let mut map = self.delegate.serialize_map(Some(2))?;
-        map.serialize_entry(self.tag, self.variant)?;
+        map.serialize_entry(self.tag, &self.variant)?;
         map.serialize_entry(inner_variant, &())?;
         map.end()
     }
@@ -180,21 +234,21 @@ where
         T: ?Sized + Serialize,
     {
         let mut map = self.delegate.serialize_map(Some(2))?;
-        map.serialize_entry(self.tag, self.variant)?;
+        map.serialize_entry(self.tag, &self.variant)?;
         map.serialize_entry(inner_variant, inner_value)?;
         map.end()
     }
 
     fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq, Self::Error> {
         let mut map = self.delegate.serialize_map(Some(2))?;
-        map.serialize_entry(self.tag, self.variant)?;
+        map.serialize_entry(self.tag, &self.variant)?;
         map.serialize_key(DEFAULT_KEY)?;
         Ok(SerializeSeqAsMapValue::new(map, len))
     }
 
     fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple, Self::Error> {
         let mut map = self.delegate.serialize_map(Some(2))?;
-        map.serialize_entry(self.tag, self.variant)?;
+        map.serialize_entry(self.tag, &self.variant)?;
         map.serialize_key(DEFAULT_KEY)?;
         Ok(SerializeTupleAsMapValue::new(map, len))
     }
@@ -205,7 +259,7 @@ where
         len: usize,
     ) -> Result<Self::SerializeTupleStruct, Self::Error> {
         let mut map = self.delegate.serialize_map(Some(2))?;
-        map.serialize_entry(self.tag, self.variant)?;
+        map.serialize_entry(self.tag, &self.variant)?;
         map.serialize_key(DEFAULT_KEY)?;
         Ok(SerializeTupleStructAsMapValue::new(map, name, len))
     }
@@ -218,14 +272,14 @@ where
         len: usize,
     ) -> Result<Self::SerializeTupleVariant, Self::Error> {
         let mut map = self.delegate.serialize_map(Some(2))?;
-        map.serialize_entry(self.tag, self.variant)?;
+        map.serialize_entry(self.tag, &self.variant)?;
         map.serialize_key(name)?;
         Ok(SerializeTupleStructAsMapValue::new(map, name, len))
     }
 
     fn serialize_map(self, len: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
         let mut map = self.delegate.serialize_map(len.map(|len| len + 1))?;
-        map.serialize_entry(self.tag, self.variant)?;
+        map.serialize_entry(self.tag, &self.variant)?;
         Ok(map)
     }
 
@@ -235,7 +289,7 @@ where
         len: usize,
     ) -> Result<Self::SerializeStruct, Self::Error> {
         let mut state = self.delegate.serialize_map(Some(len + 1))?;
-        state.serialize_entry(self.tag, self.variant)?;
+        state.serialize_entry(self.tag, &self.variant)?;
         Ok(SerializeStructAsMap::new(state, self.tag, self.variant))
     }
 
@@ -247,7 +301,7 @@ where
         len: usize,
     ) -> Result<Self::SerializeStructVariant, Self::Error> {
         let mut map = self.delegate.serialize_map(Some(2))?;
-        map.serialize_entry(self.tag, self.variant)?;
+        map.serialize_entry(self.tag, &self.variant)?;
         map.serialize_key(name)?;
         Ok(SerializeStructVariantAsMapValue::new(map, name, len))
     }
@@ -387,19 +441,19 @@ where
     }
 }
 
-pub struct SerializeStructAsMap<M> {
+pub struct SerializeStructAsMap<'a, M> {
     map: M,
     tag: &'static str,
-    variant: &'static str,
+    variant: VariantTag<'a>,
 }
 
-impl<M> SerializeStructAsMap<M> {
-    fn new(map: M, tag: &'static str, variant: &'static str) -> Self {
+impl<'a, M> SerializeStructAsMap<'a, M> {
+    fn new(map: M, tag: &'static str, variant: VariantTag<'a>) -> Self {
         SerializeStructAsMap { map, tag, variant }
     }
 }
 
-impl<M> SerializeStruct for SerializeStructAsMap<M>
+impl<'a, M> SerializeStruct for SerializeStructAsMap<'a, M>
 where
     M: SerializeMap,
 {
@@ -411,11 +465,11 @@ where
         T: ?Sized + Serialize,
     {
         if key == self.tag {
-            match expect_str(value, self.variant) {
+            match expect_str(value, self.variant.name) {
                 Ok(()) => Ok(()),
                 Err(unexpected) => Err(ser::Error::custom(format!(
                     "mismatched value for tag {:?}: {:?} vs {:?}",
-                    self.tag, self.variant, unexpected,
+                    self.tag, self.variant.name, unexpected,
                 ))),
             }
         } else {
@@ -0,0 +1,102 @@
+use alloc::vec::Vec;
+use core::fmt::{self, Display};
+
+// Beyond this many registered names, the list of expected tags in an error is
+// cut short.
+const MAX_EXPECTED: usize = 16;
+
+// How many of the closest names an error proposes.
+const MAX_SUGGESTIONS: usize = 3;
+
+// Error message for a tag that is not registered, in the style of
+// serde::de::Error::unknown_variant.
+pub struct UnknownTag<'a> {
+    pub tag: &'a str,
+    pub names: &'static [&'static str],
+    pub suggest: bool,
+}
+
+impl<'a> Display for UnknownTag<'a> {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "unknown variant `{}`", self.tag)?;
+
+        let suggestions = if self.suggest {
+            suggestions(self.tag, self.names)
+        } else {
+            Vec::new()
+        };
+        if let [suggestion] = suggestions[..] {
+            return write!(formatter, ", did you mean `{}`?", suggestion);
+        } else if !suggestions.is_empty() {
+            formatter.write_str(", did you mean one of ")?;
+            write_list(formatter, &suggestions)?;
+            return formatter.write_str("?");
+        }
+
+        match self.names {
+            [] => formatter.write_str(", there are no variants"),
+            [name] => write!(formatter, ", expected `{}`", name),
+            [first, second] => write!(formatter, ", expected `{}` or `{}`", first, second),
+            names if names.len() <= MAX_EXPECTED => {
+                formatter.write_str(", expected one of ")?;
+                write_list(formatter, names)
+            }
+            names => {
+                formatter.write_str(", expected one of ")?;
+                write_list(formatter, &names[..MAX_EXPECTED])?;
+                write!(formatter, ", and {} more", names.len() - MAX_EXPECTED)
+            }
+        }
+    }
+}
+
+fn write_list(formatter: &mut fmt::Formatter, names: &[&str]) -> fmt::Result {
+    for (i, name) in names.iter().enumerate() {
+        if i > 0 {
+            formatter.write_str(", ")?;
+        }
+        write!(formatter, "`{}`", name)?;
+    }
+    Ok(())
+}
+
+// Registered names close enough to the unknown tag to plausibly be what was
+// meant, closest first. Like rustc, allow one edit per three characters, but
+// never as many edits as the tag is long.
+fn suggestions(tag: &str, names: &'static [&'static str]) -> Vec<&'static str> {
+    let len = tag.chars().count();
+    let max_distance = (len.max(3) / 3).min(len.saturating_sub(1));
+    let mut candidates: Vec<(usize, &'static str)> = names
+        .iter()
+        .filter_map(|&name| {
+            let distance = edit_distance(tag, name);
+            if distance <= max_distance {
+                Some((distance, name))
+            } else {
+                None
+            }
+        })
+        .collect();
+    candidates.sort_unstable();
+    candidates
+        .into_iter()
+        .take(MAX_SUGGESTIONS)
+        .map(|(_distance, name)| name)
+        .collect()
+}
+
+// Levenshtein distance, ignoring ASCII case.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().map(|ch| ch.to_ascii_lowercase()).collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, a_ch) in a.chars().map(|ch| ch.to_ascii_lowercase()).enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, &b_ch) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(a_ch != b_ch);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+    row[b.len()]
+}
@@ -0,0 +1,88 @@
+use crate::content::Content;
+use alloc::string::String;
+use core::fmt::{self, Debug};
+use serde::ser::{Serialize, Serializer};
+
+/// A trait object whose tag was not recognized during deserialization.
+///
+/// Traits declared with `#[typetag::serde(unknown = MyType)]` deserialize
+/// unrecognized tags into `MyType` by way of `From<typetag::Unknown>`, instead
+/// of failing. The payload is buffered as-is, so the value serializes back to
+/// the same tag and contents it was read from.
+///
+/// The fallback type needs an impl of the trait marked
+/// `#[typetag::serde(unknown)]`, which takes its tag from
+/// `AsRef<typetag::Unknown>`. `Unknown` itself can serve as the fallback type.
+///
+/// ```
+/// # use serde::{Deserialize, Serialize};
+/// #
+/// #[typetag::serde(tag = "type", unknown = typetag::Unknown)]
+/// trait WebEvent {
+///     fn is_known(&self) -> bool {
+///         true
+///     }
+/// }
+///
+/// #[derive(Serialize, Deserialize)]
+/// struct PageLoad;
+///
+/// #[typetag::serde]
+/// impl WebEvent for PageLoad {}
+///
+/// #[typetag::serde(unknown)]
+/// impl WebEvent for typetag::Unknown {
+///     fn is_known(&self) -> bool {
+///         false
+///     }
+/// }
+///
+/// fn main() {
+///     let json = r#"{"type":"Scroll","dy":-40}"#;
+///     let event: Box<dyn WebEvent> = serde_json::from_str(json).unwrap();
+///     assert!(!event.is_known());
+///     assert_eq!(serde_json::to_string(&event).unwrap(), json);
+/// }
+/// ```
+///
+/// Buffering the payload requires a self-describing data format such as JSON.
+pub struct Unknown {
+    tag: String,
+    content: Content<'static>,
+}
+
+impl Unknown {
+    pub(crate) fn new(tag: String, content: Content<'static>) -> Self {
+        Unknown { tag, content }
+    }
+
+    /// The unrecognized tag.
+    pub fn tag(&self) -> &str {
+        &self.tag
+    }
+}
+
+impl AsRef<Unknown> for Unknown {
+    fn as_ref(&self) -> &Unknown {
+        self
+    }
+}
+
+impl Debug for Unknown {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter
+            .debug_struct("Unknown")
+            .field("tag", &self.tag)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Serializes the buffered payload, without the tag.
+impl Serialize for Unknown {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        self.content.serialize(serializer)
+    }
+}
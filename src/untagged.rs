@@ -0,0 +1,73 @@
+use crate::content::{Budget, ContentRefDeserializer, ContentSeed};
+use crate::de::Scope;
+use crate::depth;
+use crate::private::Registry;
+use crate::ser::Wrap;
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::fmt;
+use serde::de::{self, DeserializeSeed, Deserializer};
+use serde::ser::{Serialize, Serializer};
+
+pub fn serialize<S, T>(serializer: S, concrete: &T) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+    T: ?Sized + erased_serde::Serialize,
+{
+    Wrap(concrete).serialize(serializer)
+}
+
+pub fn deserialize<'de, D, T>(
+    deserializer: D,
+    trait_object: &'static str,
+    registry: &'static Registry<T>,
+    scope: Scope,
+) -> Result<Box<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: ?Sized,
+{
+    let _depth = depth::enter(trait_object, &registry.limits)?;
+    let budget = Budget::new(trait_object, registry.limits);
+    let content = ContentSeed::new(&budget).deserialize(deserializer)?;
+
+    // Registrations are kept in the order they are tried: by descending
+    // priority, then by name. Each one reads the same buffered content.
+    let mut failures = Vec::new();
+    for registration in &registry.registrations {
+        if !scope.allows(registration.name) {
+            continue;
+        }
+        let content_deserializer = ContentRefDeserializer::<D::Error>::new(&content);
+        let mut erased = <dyn erased_serde::Deserializer>::erase(content_deserializer);
+        match registration.deserialize_with(scope.context, &mut erased) {
+            Ok(value) => return Ok(value),
+            Err(err) => failures.push((registration.name, err)),
+        }
+    }
+
+    Err(de::Error::custom(NoMatch {
+        trait_object,
+        failures,
+    }))
+}
+
+struct NoMatch {
+    trait_object: &'static str,
+    failures: Vec<(&'static str, erased_serde::Error)>,
+}
+
+impl fmt::Display for NoMatch {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            formatter,
+            "data did not match any impl of untagged dyn {}",
+            self.trait_object,
+        )?;
+        for (i, (name, err)) in self.failures.iter().enumerate() {
+            let separator = if i == 0 { ": " } else { "; " };
+            write!(formatter, "{}{}: {}", separator, name, err)?;
+        }
+        Ok(())
+    }
+}
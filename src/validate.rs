@@ -0,0 +1,222 @@
+use crate::private::Registered;
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+use core::any;
+use core::fmt::{self, Debug, Display};
+
+/// Check that every tag registered for a typetag trait object belongs to
+/// exactly one impl.
+///
+/// Duplicate tags are otherwise only noticed when the first input using one is
+/// deserialized. Calling this at startup or from a test reports all of them at
+/// once, with the impls responsible for each. Aliases and numeric `id = N`
+/// tags are checked the same as names. A tag registered by impls of different
+/// `priority = N` belongs to the highest and is only reported if several impls
+/// share that priority.
+///
+/// ```
+/// # use serde::{Deserialize, Serialize};
+/// #
+/// #[typetag::serde]
+/// trait WebEvent {}
+///
+/// #[derive(Serialize, Deserialize)]
+/// struct PageLoad;
+///
+/// #[typetag::serde]
+/// impl WebEvent for PageLoad {}
+///
+/// #[derive(Serialize, Deserialize)]
+/// struct Click;
+///
+/// #[typetag::serde(alias = "PageLoad")]
+/// impl WebEvent for Click {}
+///
+/// fn main() {
+///     let err = typetag::validate::<dyn WebEvent>().unwrap_err();
+///     assert_eq!(err.collisions().len(), 1);
+///     assert_eq!(err.collisions()[0].tag(), Some("PageLoad"));
+///     assert_eq!(err.collisions()[0].origins().len(), 2);
+/// }
+/// ```
+pub fn validate<T>() -> Result<(), Collisions>
+where
+    T: ?Sized + Registered,
+{
+    let registry = T::registry();
+    let mut tags: BTreeMap<Key, (i32, Vec<Origin>)> = BTreeMap::new();
+    // Registrations are sorted by descending priority, so the first one seen
+    // for each tag sets the priority it is contested at.
+    for registration in &registry.registrations {
+        let names = registration.tags().into_iter().map(Key::Tag);
+        for key in names.chain(registration.id.map(Key::Id)) {
+            let (priority, origins) = tags
+                .entry(key)
+                .or_insert_with(|| (registration.priority, Vec::new()));
+            if registration.priority == *priority {
+                origins.push(registration.origin);
+            }
+        }
+    }
+
+    let collisions: Vec<Collision> = tags
+        .into_iter()
+        .filter(|(_key, (_priority, origins))| origins.len() > 1)
+        .map(|(key, (_priority, origins))| Collision { key, origins })
+        .collect();
+    if collisions.is_empty() {
+        Ok(())
+    } else {
+        Err(Collisions {
+            object: any::type_name::<T>(),
+            collisions,
+        })
+    }
+}
+
+/// Error returned by [`typetag::validate`][validate], listing every tag or id
+/// that was registered more than once.
+#[derive(Debug)]
+pub struct Collisions {
+    object: &'static str,
+    collisions: Vec<Collision>,
+}
+
+impl Collisions {
+    /// The colliding tags in sorted order, followed by the colliding ids.
+    pub fn collisions(&self) -> &[Collision] {
+        &self.collisions
+    }
+}
+
+impl Display for Collisions {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "duplicate tags of {}:", self.object)?;
+        for collision in &self.collisions {
+            match collision.key {
+                Key::Tag(tag) => write!(formatter, " `{}` registered by ", tag)?,
+                Key::Id(id) => write!(formatter, " id {} registered by ", id)?,
+            }
+            for (i, origin) in collision.origins.iter().enumerate() {
+                if i > 0 {
+                    formatter.write_str(", ")?;
+                }
+                Display::fmt(origin, formatter)?;
+            }
+            formatter.write_str(";")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Collisions {}
+
+/// A tag or id registered by more than one impl.
+#[derive(Debug)]
+pub struct Collision {
+    key: Key,
+    origins: Vec<Origin>,
+}
+
+// Names and aliases sort before ids.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+enum Key {
+    Tag(&'static str),
+    Id(u32),
+}
+
+impl Collision {
+    /// The name or alias that is registered more than once, or `None` if the
+    /// collision is over an [`id`][Self::id].
+    pub fn tag(&self) -> Option<&'static str> {
+        match self.key {
+            Key::Tag(tag) => Some(tag),
+            Key::Id(_) => None,
+        }
+    }
+
+    /// The numeric id that is registered more than once, or `None` if the
+    /// collision is over a [`tag`][Self::tag].
+    pub fn id(&self) -> Option<u32> {
+        match self.key {
+            Key::Tag(_) => None,
+            Key::Id(id) => Some(id),
+        }
+    }
+
+    /// The impls registering this tag at its highest priority.
+    pub fn origins(&self) -> &[Origin] {
+        &self.origins
+    }
+}
+
+/// Where an impl was registered for deserialization.
+#[derive(Copy, Clone)]
+pub struct Origin {
+    type_name: fn() -> &'static str,
+    module_path: &'static str,
+    file: &'static str,
+    line: u32,
+}
+
+impl Origin {
+    #[doc(hidden)]
+    pub const fn new(
+        type_name: fn() -> &'static str,
+        module_path: &'static str,
+        file: &'static str,
+        line: u32,
+    ) -> Self {
+        Origin {
+            type_name,
+            module_path,
+            file,
+            line,
+        }
+    }
+
+    /// The type the impl is for, as given by [`core::any::type_name`].
+    pub fn type_name(&self) -> &'static str {
+        (self.type_name)()
+    }
+
+    /// The module containing the impl, as given by `module_path!()`.
+    pub fn module_path(&self) -> &'static str {
+        self.module_path
+    }
+
+    /// The source file containing the impl's typetag attribute.
+    pub fn file(&self) -> &'static str {
+        self.file
+    }
+
+    /// The line of the impl's typetag attribute within [`file`][Self::file].
+    pub fn line(&self) -> u32 {
+        self.line
+    }
+}
+
+impl Debug for Origin {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter
+            .debug_struct("Origin")
+            .field("type_name", &self.type_name())
+            .field("module_path", &self.module_path)
+            .field("file", &self.file)
+            .field("line", &self.line)
+            .finish()
+    }
+}
+
+impl Display for Origin {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            formatter,
+            "`{}` at {}:{}",
+            self.type_name(),
+            self.file,
+            self.line,
+        )
+    }
+}
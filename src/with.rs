@@ -0,0 +1,185 @@
+//! Paths for `#[serde(with = "...")]` that pick the tag representation of a
+//! `Box<dyn Trait>` field at the use site, independent of the one declared on
+//! the trait.
+//!
+//! ```
+//! # use serde::{Deserialize, Serialize};
+//! #
+//! #[typetag::serde]
+//! trait WebEvent {}
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct PageLoad;
+//!
+//! #[typetag::serde]
+//! impl WebEvent for PageLoad {}
+//!
+//! struct Kind;
+//!
+//! impl typetag::with::Key for Kind {
+//!     const NAME: &'static str = "kind";
+//! }
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Stored {
+//!     #[serde(with = "typetag::with::Internally::<Kind>")]
+//!     event: Box<dyn WebEvent>,
+//! }
+//!
+//! fn main() {
+//!     let stored = Stored {
+//!         event: Box::new(PageLoad),
+//!     };
+//!     let json = serde_json::to_string(&stored).unwrap();
+//!     assert_eq!(json, r#"{"event":{"kind":"PageLoad"}}"#);
+//! }
+//! ```
+//!
+//! Values are deserialized without a context, and only the trait's own tag
+//! names and ids are recognized; options such as `default_variant` and
+//! `deny_unknown_fields` belong to the trait's own representation.
+
+// Serde passes the field by reference, and only the Box gives T a name.
+#![allow(clippy::borrowed_box)]
+
+use crate::de::Scope;
+use crate::private::{Deserializable, Registered, Serializable};
+use alloc::boxed::Box;
+use core::marker::PhantomData;
+use serde::{Deserializer, Serializer};
+
+/// The name of a map key used by [`Internally`] and [`Adjacently`].
+pub trait Key {
+    const NAME: &'static str;
+}
+
+/// Externally tagged: `{"PageLoad":{...}}`.
+pub struct Externally {
+    _private: (),
+}
+
+impl Externally {
+    pub fn serialize<S, T>(value: &Box<T>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+        T: ?Sized + Serializable,
+    {
+        let value = &**value;
+        crate::externally::serialize(serializer, value.typetag_name(), value.typetag_id(), value)
+    }
+
+    pub fn deserialize<'de, D, T>(deserializer: D) -> Result<Box<T>, D::Error>
+    where
+        D: Deserializer<'de>,
+        T: ?Sized + Deserializable,
+    {
+        let registry = <T::Registered as Registered>::registry();
+        crate::externally::deserialize(deserializer, T::NAME, registry, Scope::default())
+            .map(T::from_strictest)
+    }
+}
+
+/// Same as [`Externally`], for `#[serde(with = "typetag::with::externally")]`.
+pub mod externally {
+    use super::*;
+
+    pub fn serialize<S, T>(value: &Box<T>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+        T: ?Sized + Serializable,
+    {
+        Externally::serialize(value, serializer)
+    }
+
+    pub fn deserialize<'de, D, T>(deserializer: D) -> Result<Box<T>, D::Error>
+    where
+        D: Deserializer<'de>,
+        T: ?Sized + Deserializable,
+    {
+        Externally::deserialize(deserializer)
+    }
+}
+
+/// Internally tagged under the key `K`: `{"kind":"PageLoad",...}`.
+pub struct Internally<K> {
+    key: PhantomData<K>,
+}
+
+impl<K: Key> Internally<K> {
+    pub fn serialize<S, T>(value: &Box<T>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+        T: ?Sized + Serializable,
+    {
+        let value = &**value;
+        crate::internally::serialize(
+            serializer,
+            K::NAME,
+            value.typetag_name(),
+            value.typetag_id(),
+            value,
+        )
+    }
+
+    pub fn deserialize<'de, D, T>(deserializer: D) -> Result<Box<T>, D::Error>
+    where
+        D: Deserializer<'de>,
+        T: ?Sized + Deserializable,
+    {
+        let registry = <T::Registered as Registered>::registry();
+        crate::internally::deserialize(
+            deserializer,
+            T::NAME,
+            K::NAME,
+            None,
+            registry,
+            Scope::default(),
+        )
+        .map(T::from_strictest)
+    }
+}
+
+/// Adjacently tagged under the keys `K` and `C`:
+/// `{"kind":"PageLoad","value":{...}}`.
+pub struct Adjacently<K, C> {
+    keys: PhantomData<(K, C)>,
+}
+
+impl<K: Key, C: Key> Adjacently<K, C> {
+    const FIELDS: &'static [&'static str; 2] = &[K::NAME, C::NAME];
+
+    pub fn serialize<S, T>(value: &Box<T>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+        T: ?Sized + Serializable,
+    {
+        let value = &**value;
+        crate::adjacently::serialize(
+            serializer,
+            T::NAME,
+            K::NAME,
+            value.typetag_name(),
+            value.typetag_id(),
+            C::NAME,
+            value,
+        )
+    }
+
+    pub fn deserialize<'de, D, T>(deserializer: D) -> Result<Box<T>, D::Error>
+    where
+        D: Deserializer<'de>,
+        T: ?Sized + Deserializable,
+    {
+        let registry = <T::Registered as Registered>::registry();
+        crate::adjacently::deserialize(
+            deserializer,
+            T::NAME,
+            Self::FIELDS,
+            None,
+            registry,
+            false,
+            Scope::default(),
+        )
+        .map(T::from_strictest)
+    }
+}
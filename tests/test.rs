@@ -142,6 +142,43 @@ mod internally_tagged {
     }
 }
 
+mod wide_integers {
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Serialize, Deserialize)]
+    struct Wide {
+        u: u128,
+        i: i128,
+    }
+
+    #[typetag::serde(tag = "type")]
+    trait Trait {
+        fn assert_extremes(&self);
+    }
+
+    #[typetag::serde]
+    impl Trait for Wide {
+        fn assert_extremes(&self) {
+            assert_eq!(self.u, u128::MAX);
+            assert_eq!(self.i, i128::MIN);
+        }
+    }
+
+    #[test]
+    fn test_ron_tag_first() {
+        let ron = r#"{"type": "Wide", "u": 340282366920938463463374607431768211455, "i": -170141183460469231731687303715884105728}"#;
+        let trait_object: Box<dyn Trait> = ron::from_str(ron).unwrap();
+        trait_object.assert_extremes();
+    }
+
+    #[test]
+    fn test_ron_tag_last() {
+        let ron = r#"{"u": 340282366920938463463374607431768211455, "i": -170141183460469231731687303715884105728, "type": "Wide"}"#;
+        let trait_object: Box<dyn Trait> = ron::from_str(ron).unwrap();
+        trait_object.assert_extremes();
+    }
+}
+
 mod adjacently_tagged {
     use super::{A, B};
 
@@ -479,140 +516,2305 @@ mod marker_traits {
     }
 }
 
-mod generic {
-    #[typetag::serialize]
-    trait Generic<T> {}
-}
+#[cfg(feature = "rc")]
+mod shared {
+    use super::A;
+    use serde::de::DeserializeOwned;
+    use std::rc::Rc;
+    use std::sync::Arc;
 
-#[rustversion::since(1.74)]
-mod assoc_type {
     #[typetag::serde]
-    trait Trait {
-        type AssocType
-        where
-            Self: Sized;
+    trait Node: Send + Sync {
+        fn assert_a_is_11(&self);
     }
-}
-
-mod macro_expanded {
-    use super::A;
 
     #[typetag::serde]
-    trait Trait {}
+    impl Node for A {
+        fn assert_a_is_11(&self) {
+            assert_eq!(self.a, 11);
+        }
+    }
 
-    macro_rules! impl_trait {
-        ($ty:ty) => {
-            #[typetag::serde]
-            impl Trait for $ty {}
-        };
+    fn assert_deserialize<T>()
+    where
+        T: ?Sized,
+        Arc<T>: DeserializeOwned,
+        Rc<T>: DeserializeOwned,
+    {
     }
 
-    impl_trait!(A);
+    #[test]
+    fn test_deserialize() {
+        assert_deserialize::<dyn Node>();
+        assert_deserialize::<dyn Node + Send>();
+        assert_deserialize::<dyn Node + Sync>();
+        assert_deserialize::<dyn Node + Send + Sync>();
+    }
+
+    #[test]
+    fn test_json_arc() {
+        let node: Arc<dyn Node + Send + Sync> = Arc::new(A { a: 11 });
+        let json = serde_json::to_string(&node).unwrap();
+        assert_eq!(json, r#"{"A":{"a":11}}"#);
+        let node: Arc<dyn Node + Send + Sync> = serde_json::from_str(&json).unwrap();
+        node.assert_a_is_11();
+    }
+
+    #[test]
+    fn test_json_rc() {
+        let json = r#"{"A":{"a":11}}"#;
+        let node: Rc<dyn Node> = serde_json::from_str(json).unwrap();
+        node.assert_a_is_11();
+    }
 }
 
-// https://github.com/dtolnay/typetag/issues/28
-mod trait_hierarchy {
+mod generic {
+    #[typetag::serialize]
+    trait Generic<T> {}
+}
+
+mod generic_instantiate {
     use serde::{Deserialize, Serialize};
 
-    #[typetag::serde]
-    pub trait Base {}
+    #[derive(Serialize, Deserialize)]
+    struct Ping;
 
     #[derive(Serialize, Deserialize)]
-    struct SomeBase;
+    struct Pong;
 
-    #[typetag::serde]
-    impl Base for SomeBase {}
+    #[typetag::serde(instantiate(Handler<Ping>, Handler<Pong>))]
+    trait Handler<M> {
+        fn handle(&self, message: M) -> &'static str;
+    }
 
-    #[typetag::serde]
-    pub trait Derived: Base {}
+    #[derive(Serialize, Deserialize)]
+    struct A;
 
     #[derive(Serialize, Deserialize)]
-    struct SomeDerived;
+    struct B;
 
     #[typetag::serde]
-    impl Base for SomeDerived {}
+    impl Handler<Ping> for A {
+        fn handle(&self, _message: Ping) -> &'static str {
+            "A got ping"
+        }
+    }
 
     #[typetag::serde]
-    impl Derived for SomeDerived {}
+    impl Handler<Pong> for A {
+        fn handle(&self, _message: Pong) -> &'static str {
+            "A got pong"
+        }
+    }
+
+    #[typetag::serde]
+    impl Handler<Pong> for B {
+        fn handle(&self, _message: Pong) -> &'static str {
+            "B got pong"
+        }
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        let trait_object = &A as &dyn Handler<Ping>;
+        let json = serde_json::to_string(trait_object).unwrap();
+        assert_eq!(json, r#"{"A":null}"#);
+        let handler: Box<dyn Handler<Ping>> = serde_json::from_str(&json).unwrap();
+        assert_eq!(handler.handle(Ping), "A got ping");
+
+        let json = r#"{"B":null}"#;
+        let handler: Box<dyn Handler<Pong>> = serde_json::from_str(json).unwrap();
+        assert_eq!(handler.handle(Pong), "B got pong");
+    }
+
+    #[test]
+    fn test_separate_registries() {
+        let json = r#"{"B":null}"#;
+        assert!(serde_json::from_str::<Box<dyn Handler<Ping>>>(json).is_err());
+
+        let ping = typetag::registry::<dyn Handler<Ping>>().map(|tag| tag.name());
+        assert_eq!(ping.collect::<Vec<_>>(), ["A"]);
+        let pong = typetag::registry::<dyn Handler<Pong>>().map(|tag| tag.name());
+        assert_eq!(pong.collect::<Vec<_>>(), ["A", "B"]);
+    }
 }
 
-mod tag_mismatch {
-    use serde::Serialize;
+mod generic_instances {
+    use serde::de::DeserializeOwned;
+    use serde::{Deserialize, Serialize};
 
-    #[typetag::serialize(tag = "type")]
-    trait Trait {}
+    trait Codec: Serialize + DeserializeOwned {}
 
-    #[derive(Serialize)]
-    struct Tagged<T> {
-        #[serde(rename = "type")]
-        t: T,
+    impl Codec for u8 {}
+    impl Codec for String {}
+
+    #[typetag::serde]
+    trait Transform {
+        fn describe(&self) -> String;
     }
 
-    #[typetag::serialize]
-    impl<T: Serialize> Trait for Tagged<T> {}
+    #[derive(Serialize, Deserialize)]
+    struct Wrapper<T> {
+        value: T,
+    }
+
+    #[typetag::serde(instances(
+        Wrapper<u8> = ("WrapperU8", id = 1),
+        Wrapper<String> = ("WrapperStr", id = 2),
+    ))]
+    impl<T: Codec> Transform for Wrapper<T> {
+        fn describe(&self) -> String {
+            serde_json::to_string(&self.value).unwrap()
+        }
+    }
+
+    #[typetag::serde(instantiate(Handler<u8>, Handler<bool>))]
+    trait Handler<M> {
+        fn handle(&self, message: M) -> bool;
+    }
+
+    #[derive(Serialize, Deserialize)]
+    struct Expect<M> {
+        message: M,
+    }
+
+    #[typetag::serde(instances(Expect<u8> = "ExpectU8", Expect<bool> = "ExpectBool"))]
+    impl<M: PartialEq + Serialize> Handler<M> for Expect<M> {
+        fn handle(&self, message: M) -> bool {
+            self.message == message
+        }
+    }
 
     #[test]
-    fn test_json_serialize() {
-        let trait_object = &Tagged { t: "Tagged" } as &dyn Trait;
+    fn test_json_round_trip() {
+        let trait_object = &Wrapper { value: 7u8 } as &dyn Transform;
         let json = serde_json::to_string(trait_object).unwrap();
-        let expected = r#"{"type":"Tagged"}"#;
-        assert_eq!(json, expected);
+        assert_eq!(json, r#"{"WrapperU8":{"value":7}}"#);
+        let transform: Box<dyn Transform> = serde_json::from_str(&json).unwrap();
+        assert_eq!(transform.describe(), "7");
 
-        let trait_object = &Tagged { t: "Mismatch" } as &dyn Trait;
-        let err = serde_json::to_string(trait_object).unwrap_err();
-        let expected = r#"mismatched value for tag "type": "Tagged" vs "Mismatch""#;
-        assert_eq!(err.to_string(), expected);
+        let trait_object = &Wrapper {
+            value: "x".to_owned(),
+        } as &dyn Transform;
+        let json = serde_json::to_string(trait_object).unwrap();
+        assert_eq!(json, r#"{"WrapperStr":{"value":"x"}}"#);
+        let transform: Box<dyn Transform> = serde_json::from_str(&json).unwrap();
+        assert_eq!(transform.describe(), r#""x""#);
+    }
 
-        let trait_object = &Tagged { t: false } as &dyn Trait;
-        let err = serde_json::to_string(trait_object).unwrap_err();
-        let expected = r#"mismatched value for tag "type": "Tagged" vs non-string"#;
-        assert_eq!(err.to_string(), expected);
+    #[test]
+    fn test_postcard_ids() {
+        let trait_object = &Wrapper {
+            value: "x".to_owned(),
+        } as &dyn Transform;
+        let bytes = postcard::to_stdvec(trait_object).unwrap();
+        assert_eq!(bytes, [1, 2, 1, b'x']);
+        let transform: Box<dyn Transform> = postcard::from_bytes(&bytes).unwrap();
+        assert_eq!(transform.describe(), r#""x""#);
+    }
+
+    #[test]
+    fn test_generic_trait() {
+        let json = r#"{"ExpectBool":{"message":true}}"#;
+        let handler: Box<dyn Handler<bool>> = serde_json::from_str(json).unwrap();
+        assert!(handler.handle(true));
+        assert_eq!(serde_json::to_string(&handler).unwrap(), json);
+
+        let tags = typetag::registry::<dyn Handler<u8>>().map(|tag| tag.name());
+        assert_eq!(tags.collect::<Vec<_>>(), ["ExpectU8"]);
     }
 }
 
-mod async_traits {
-    use async_trait::async_trait;
+mod aliases {
+    use super::{A, B};
     use serde::{Deserialize, Serialize};
 
     #[derive(Serialize, Deserialize)]
-    pub struct InOrder;
-
-    #[derive(Serialize, Deserialize)]
-    pub struct OutOfOrder;
+    struct Click {
+        x: i32,
+    }
 
     #[typetag::serde]
-    #[async_trait]
-    trait TypetagAsync {
-        async fn f(&self) {}
+    trait Externally {
+        fn x(&self) -> i32;
     }
 
-    #[typetag::serde]
-    #[async_trait]
-    impl TypetagAsync for InOrder {
-        async fn f(&self) {}
+    #[typetag::serde(tag = "type")]
+    trait Internally {
+        fn x(&self) -> i32;
     }
 
-    #[async_trait]
-    #[typetag::serde]
-    impl TypetagAsync for OutOfOrder {
-        async fn f(&self) {}
+    #[typetag::serde(tag = "type", content = "content")]
+    trait Adjacently {
+        fn x(&self) -> i32;
+    }
+
+    #[typetag::serde(name = "Click", alias = "MouseClick", alias = "click_v1")]
+    impl Externally for Click {
+        fn x(&self) -> i32 {
+            self.x
+        }
+    }
+
+    #[typetag::serde(alias = "MouseClick")]
+    impl Internally for Click {
+        fn x(&self) -> i32 {
+            self.x
+        }
+    }
+
+    #[typetag::serde(alias = "MouseClick")]
+    impl Adjacently for Click {
+        fn x(&self) -> i32 {
+            self.x
+        }
+    }
+
+    #[test]
+    fn test_json_deserialize_alias() {
+        let json = r#"{"MouseClick":{"x":1}}"#;
+        let trait_object: Box<dyn Externally> = serde_json::from_str(json).unwrap();
+        assert_eq!(trait_object.x(), 1);
+
+        let json = r#"{"click_v1":{"x":2}}"#;
+        let trait_object: Box<dyn Externally> = serde_json::from_str(json).unwrap();
+        assert_eq!(trait_object.x(), 2);
+
+        let json = r#"{"type":"MouseClick","x":3}"#;
+        let trait_object: Box<dyn Internally> = serde_json::from_str(json).unwrap();
+        assert_eq!(trait_object.x(), 3);
+
+        let json = r#"{"type":"MouseClick","content":{"x":4}}"#;
+        let trait_object: Box<dyn Adjacently> = serde_json::from_str(json).unwrap();
+        assert_eq!(trait_object.x(), 4);
+    }
+
+    #[test]
+    fn test_json_serialize_name() {
+        let trait_object = &Click { x: 1 } as &dyn Externally;
+        let json = serde_json::to_string(trait_object).unwrap();
+        assert_eq!(json, r#"{"Click":{"x":1}}"#);
+
+        let trait_object = &Click { x: 1 } as &dyn Internally;
+        let json = serde_json::to_string(trait_object).unwrap();
+        assert_eq!(json, r#"{"type":"Click","x":1}"#);
+
+        let tags = typetag::registry::<dyn Externally>().map(|tag| tag.name());
+        assert_eq!(tags.collect::<Vec<_>>(), ["Click"]);
     }
 
-    #[async_trait]
     #[typetag::serde]
-    trait AsyncTypetag {
-        async fn f(&self) {}
+    trait Colliding {}
+
+    #[typetag::serde]
+    impl Colliding for A {}
+
+    #[typetag::serde(alias = "A")]
+    impl Colliding for B {}
+
+    #[test]
+    fn test_alias_collision() {
+        let tags: Vec<(&str, bool)> = typetag::registry::<dyn Colliding>()
+            .map(|tag| (tag.name(), tag.is_duplicate()))
+            .collect();
+        assert_eq!(tags, [("A", true), ("B", false)]);
+
+        let json = r#"{"B":{"b":11}}"#;
+        assert!(serde_json::from_str::<Box<dyn Colliding>>(json).is_ok());
     }
 
-    #[async_trait]
     #[typetag::serde]
-    impl AsyncTypetag for InOrder {
-        async fn f(&self) {}
+    trait Repeated {}
+
+    #[typetag::serde(alias = "A", alias = "OldA", alias = "OldA")]
+    impl Repeated for A {}
+
+    #[test]
+    fn test_repeated_alias() {
+        typetag::validate::<dyn Repeated>().unwrap();
+
+        let json = r#"{"A":{"a":11}}"#;
+        assert!(serde_json::from_str::<Box<dyn Repeated>>(json).is_ok());
+
+        let json = r#"{"OldA":{"a":11}}"#;
+        assert!(serde_json::from_str::<Box<dyn Repeated>>(json).is_ok());
+    }
+}
+
+mod unknown {
+    use super::A;
+    use serde::Serialize;
+
+    #[typetag::serde(unknown = typetag::Unknown)]
+    trait Externally {
+        fn tag(&self) -> Option<&str> {
+            None
+        }
+    }
+
+    #[typetag::serde(tag = "type", unknown = typetag::Unknown)]
+    trait Internally {
+        fn tag(&self) -> Option<&str> {
+            None
+        }
+    }
+
+    #[typetag::serde(tag = "type", content = "content", unknown = UnknownEvent)]
+    trait Adjacently {
+        fn tag(&self) -> Option<&str> {
+            None
+        }
     }
 
     #[typetag::serde]
-    #[async_trait]
-    impl AsyncTypetag for OutOfOrder {
-        async fn f(&self) {}
+    impl Externally for A {}
+
+    #[typetag::serde]
+    impl Internally for A {}
+
+    #[typetag::serde]
+    impl Adjacently for A {}
+
+    #[typetag::serde(unknown)]
+    impl Externally for typetag::Unknown {
+        fn tag(&self) -> Option<&str> {
+            Some(typetag::Unknown::tag(self))
+        }
+    }
+
+    #[typetag::serde(unknown)]
+    impl Internally for typetag::Unknown {
+        fn tag(&self) -> Option<&str> {
+            Some(typetag::Unknown::tag(self))
+        }
+    }
+
+    #[derive(Serialize)]
+    #[serde(transparent)]
+    struct UnknownEvent(typetag::Unknown);
+
+    impl From<typetag::Unknown> for UnknownEvent {
+        fn from(unknown: typetag::Unknown) -> Self {
+            UnknownEvent(unknown)
+        }
+    }
+
+    impl AsRef<typetag::Unknown> for UnknownEvent {
+        fn as_ref(&self) -> &typetag::Unknown {
+            &self.0
+        }
+    }
+
+    #[typetag::serde(unknown)]
+    impl Adjacently for UnknownEvent {
+        fn tag(&self) -> Option<&str> {
+            Some(self.0.tag())
+        }
+    }
+
+    #[test]
+    fn test_externally_round_trip() {
+        let json = r#"{"Scroll":{"dy":-40,"smooth":[true,null]}}"#;
+        let trait_object: Box<dyn Externally> = serde_json::from_str(json).unwrap();
+        assert_eq!(trait_object.tag(), Some("Scroll"));
+        assert_eq!(serde_json::to_string(&trait_object).unwrap(), json);
+
+        let json = r#"{"A":{"a":11}}"#;
+        let trait_object: Box<dyn Externally> = serde_json::from_str(json).unwrap();
+        assert_eq!(trait_object.tag(), None);
+    }
+
+    #[test]
+    fn test_internally_round_trip() {
+        let json = r#"{"type":"Scroll","dy":-40}"#;
+        let trait_object: Box<dyn Internally> = serde_json::from_str(json).unwrap();
+        assert_eq!(trait_object.tag(), Some("Scroll"));
+        assert_eq!(serde_json::to_string(&trait_object).unwrap(), json);
+
+        // The tag need not come first.
+        let json = r#"{"dy":-40,"type":"Scroll"}"#;
+        let trait_object: Box<dyn Internally> = serde_json::from_str(json).unwrap();
+        let expected = r#"{"type":"Scroll","dy":-40}"#;
+        assert_eq!(serde_json::to_string(&trait_object).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_adjacently_round_trip() {
+        let json = r#"{"type":"Scroll","content":{"dy":-40}}"#;
+        let trait_object: Box<dyn Adjacently> = serde_json::from_str(json).unwrap();
+        assert_eq!(trait_object.tag(), Some("Scroll"));
+        assert_eq!(serde_json::to_string(&trait_object).unwrap(), json);
+
+        let json = r#"{"content":[1,2],"type":"Scroll"}"#;
+        let trait_object: Box<dyn Adjacently> = serde_json::from_str(json).unwrap();
+        let expected = r#"{"type":"Scroll","content":[1,2]}"#;
+        assert_eq!(serde_json::to_string(&trait_object).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_registry_excludes_fallback() {
+        let tags = typetag::registry::<dyn Externally>().map(|tag| tag.name());
+        assert_eq!(tags.collect::<Vec<_>>(), ["A"]);
+    }
+}
+
+mod rename_all {
+    use serde::{Deserialize, Serialize};
+
+    #[typetag::serde(tag = "type", rename_all = "snake_case")]
+    trait Snake {}
+
+    #[typetag::serde(rename_all = "SCREAMING-KEBAB-CASE")]
+    trait ScreamingKebab {}
+
+    #[typetag::serialize(rename_all = "camelCase")]
+    trait Camel {}
+
+    #[derive(Serialize, Deserialize)]
+    struct PageLoad;
+
+    #[derive(Serialize, Deserialize)]
+    struct MouseClick;
+
+    #[typetag::serde]
+    impl Snake for PageLoad {}
+
+    #[typetag::serde(name = "Click")]
+    impl Snake for MouseClick {}
+
+    #[typetag::serde]
+    impl ScreamingKebab for PageLoad {}
+
+    #[typetag::serialize]
+    impl Camel for PageLoad {}
+
+    #[test]
+    fn test_json_round_trip() {
+        let trait_object = &PageLoad as &dyn Snake;
+        let json = serde_json::to_string(trait_object).unwrap();
+        assert_eq!(json, r#"{"type":"page_load"}"#);
+        let _: Box<dyn Snake> = serde_json::from_str(&json).unwrap();
+
+        let trait_object = &PageLoad as &dyn ScreamingKebab;
+        let json = serde_json::to_string(trait_object).unwrap();
+        assert_eq!(json, r#"{"PAGE-LOAD":null}"#);
+        let _: Box<dyn ScreamingKebab> = serde_json::from_str(&json).unwrap();
+
+        let trait_object = &PageLoad as &dyn Camel;
+        let json = serde_json::to_string(trait_object).unwrap();
+        assert_eq!(json, r#"{"pageLoad":null}"#);
+    }
+
+    #[test]
+    fn test_explicit_name() {
+        let tags = typetag::registry::<dyn Snake>().map(|tag| tag.name());
+        assert_eq!(tags.collect::<Vec<_>>(), ["Click", "page_load"]);
+    }
+}
+
+mod serialize_only_generic {
+    use serde::Serialize;
+
+    #[typetag::serialize]
+    trait Boxed {}
+
+    #[typetag::serialize(rename_all = "snake_case")]
+    trait Blanket {}
+
+    // Neither impl could implement typetag's own traits for its self type
+    // without breaking the orphan rule.
+    #[typetag::serialize]
+    impl<T: Serialize + 'static> Boxed for Box<T> {}
+
+    #[typetag::serialize]
+    impl<T: Serialize> Blanket for T {}
+
+    #[test]
+    fn test_json_default_name() {
+        let trait_object = &Box::new(1) as &dyn Boxed;
+        let json = serde_json::to_string(trait_object).unwrap();
+        assert_eq!(json, r#"{"Box":1}"#);
+
+        let trait_object = &1 as &dyn Blanket;
+        let json = serde_json::to_string(trait_object).unwrap();
+        assert_eq!(json, r#"{"t":1}"#);
+    }
+}
+
+mod namespace {
+    #[typetag::serde(tag = "type", namespace = "module")]
+    trait Module {}
+
+    #[typetag::serde(namespace = "crate", rename_all = "snake_case")]
+    trait Crate {}
+
+    #[typetag::serialize(namespace = "module")]
+    trait SerializeOnly {}
+
+    mod config {
+        use serde::{Deserialize, Serialize};
+
+        #[derive(Serialize, Deserialize)]
+        pub struct Source;
+
+        #[typetag::serde]
+        impl super::Module for Source {}
+
+        #[typetag::serde]
+        impl super::Crate for Source {}
+    }
+
+    mod net {
+        use serde::{Deserialize, Serialize};
+
+        #[derive(Serialize, Deserialize)]
+        pub struct Source;
+
+        #[typetag::serde]
+        impl super::Module for Source {}
+
+        #[typetag::serde(name = "net_source")]
+        impl super::Crate for Source {}
+    }
+
+    mod generic {
+        use serde::Serialize;
+
+        #[derive(Serialize)]
+        pub struct Wrapper<T>(pub T);
+
+        #[typetag::serialize]
+        impl<T: Serialize> super::SerializeOnly for Wrapper<T> {}
+    }
+
+    #[test]
+    fn test_json_module() {
+        let trait_object = &config::Source as &dyn Module;
+        let json = serde_json::to_string(trait_object).unwrap();
+        assert_eq!(json, r#"{"type":"test::namespace::config::Source"}"#);
+        let _: Box<dyn Module> = serde_json::from_str(&json).unwrap();
+
+        let trait_object = &net::Source as &dyn Module;
+        let json = serde_json::to_string(trait_object).unwrap();
+        assert_eq!(json, r#"{"type":"test::namespace::net::Source"}"#);
+        let _: Box<dyn Module> = serde_json::from_str(&json).unwrap();
+
+        typetag::validate::<dyn Module>().unwrap();
+    }
+
+    #[test]
+    fn test_json_crate() {
+        let trait_object = &config::Source as &dyn Crate;
+        let json = serde_json::to_string(trait_object).unwrap();
+        assert_eq!(json, r#"{"test::source":null}"#);
+        let _: Box<dyn Crate> = serde_json::from_str(&json).unwrap();
+    }
+
+    #[test]
+    fn test_explicit_name() {
+        let tags = typetag::registry::<dyn Crate>().map(|tag| tag.name());
+        assert_eq!(tags.collect::<Vec<_>>(), ["net_source", "test::source"]);
+    }
+
+    #[test]
+    fn test_json_serialize_only() {
+        let trait_object = &generic::Wrapper(1) as &dyn SerializeOnly;
+        let json = serde_json::to_string(trait_object).unwrap();
+        assert_eq!(json, r#"{"test::namespace::generic::Wrapper":1}"#);
+    }
+}
+
+mod context {
+    use super::A;
+    use serde::de::{DeserializeSeed, Deserializer};
+    use serde::{Deserialize, Serialize};
+    use typetag::DeserializeWithContext;
+
+    #[typetag::serde(tag = "type")]
+    trait Trait {
+        fn value(&self) -> u8;
+    }
+
+    #[typetag::serde]
+    impl Trait for A {
+        fn value(&self) -> u8 {
+            self.a
+        }
+    }
+
+    #[derive(Serialize)]
+    struct Scaled {
+        raw: u8,
+    }
+
+    struct Scale(u8);
+
+    impl DeserializeWithContext<Scale> for Scaled {
+        fn deserialize_with_context<'de, D>(
+            scale: &Scale,
+            deserializer: D,
+        ) -> Result<Self, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            #[derive(Deserialize)]
+            struct Raw {
+                raw: u8,
+            }
+            let raw = Raw::deserialize(deserializer)?;
+            Ok(Scaled {
+                raw: raw.raw * scale.0,
+            })
+        }
+    }
+
+    #[typetag::serde(context = Scale)]
+    impl Trait for Scaled {
+        fn value(&self) -> u8 {
+            self.raw
+        }
+    }
+
+    fn from_str_seeded<Ctx: 'static>(
+        json: &str,
+        context: &Ctx,
+    ) -> serde_json::Result<Box<dyn Trait>> {
+        let mut de = serde_json::Deserializer::from_str(json);
+        typetag::seed::<dyn Trait, Ctx>(context).deserialize(&mut de)
+    }
+
+    #[test]
+    fn test_json_deserialize_seeded() {
+        let json = r#"{"type":"Scaled","raw":7}"#;
+        let trait_object = from_str_seeded(json, &Scale(3)).unwrap();
+        assert_eq!(trait_object.value(), 21);
+    }
+
+    #[test]
+    fn test_json_deserialize_seeded_ignores_context() {
+        let json = r#"{"type":"A","a":11}"#;
+        let trait_object = from_str_seeded(json, &Scale(3)).unwrap();
+        assert_eq!(trait_object.value(), 11);
+    }
+
+    #[test]
+    fn test_json_deserialize_without_context() {
+        let json = r#"{"type":"Scaled","raw":7}"#;
+        match serde_json::from_str::<Box<dyn Trait>>(json) {
+            Ok(_) => panic!("unexpectedly deserialized without a context"),
+            Err(err) => {
+                let expected = "while deserializing `Scaled` as dyn Trait: can only be deserialized with a context of type `test::context::Scale`, use typetag::seed at line 1 column 16";
+                assert_eq!(err.to_string(), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn test_json_deserialize_wrong_context() {
+        let json = r#"{"type":"Scaled","raw":7}"#;
+        match from_str_seeded(json, &()) {
+            Ok(_) => panic!("unexpectedly deserialized with the wrong context"),
+            Err(err) => {
+                let expected = "while deserializing `Scaled` as dyn Trait: expected a context of type `test::context::Scale` at line 1 column 16";
+                assert_eq!(err.to_string(), expected);
+            }
+        }
+    }
+}
+
+#[rustversion::since(1.74)]
+mod assoc_type {
+    #[typetag::serde]
+    trait Trait {
+        type AssocType
+        where
+            Self: Sized;
+    }
+}
+
+#[rustversion::since(1.74)]
+mod assoc_type_sized {
+    use super::A;
+
+    #[typetag::serde]
+    trait Trait {
+        type AssocType
+        where
+            Self: Sized;
+
+        fn assert_a_is_11(&self);
+    }
+
+    #[typetag::serde]
+    impl Trait for A {
+        type AssocType = u8;
+
+        fn assert_a_is_11(&self) {
+            assert_eq!(self.a, 11);
+        }
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        let trait_object = &A { a: 11 } as &dyn Trait;
+        let json = serde_json::to_string(trait_object).unwrap();
+        let trait_object: Box<dyn Trait> = serde_json::from_str(&json).unwrap();
+        trait_object.assert_a_is_11();
+    }
+}
+
+mod assoc_type_instantiate {
+    use serde::{Deserialize, Serialize};
+
+    #[derive(PartialEq, Debug)]
+    struct Frame(u32);
+
+    #[typetag::serde(instantiate(Stage<Output = Frame>, Stage<Output = u32>))]
+    trait Stage {
+        type Output;
+
+        fn run(&self) -> Self::Output;
+    }
+
+    #[derive(Serialize, Deserialize)]
+    struct Decoder {
+        width: u32,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    struct Counter;
+
+    #[typetag::serde]
+    impl Stage for Decoder {
+        type Output = Frame;
+
+        fn run(&self) -> Self::Output {
+            Frame(self.width)
+        }
+    }
+
+    #[typetag::serde]
+    impl Stage for Counter {
+        type Output = u32;
+
+        fn run(&self) -> Self::Output {
+            1
+        }
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        let trait_object = &Decoder { width: 640 } as &dyn Stage<Output = Frame>;
+        let json = serde_json::to_string(trait_object).unwrap();
+        assert_eq!(json, r#"{"Decoder":{"width":640}}"#);
+        let stage: Box<dyn Stage<Output = Frame>> = serde_json::from_str(&json).unwrap();
+        assert_eq!(stage.run(), Frame(640));
+
+        let json = r#"{"Counter":null}"#;
+        let stage: Box<dyn Stage<Output = u32>> = serde_json::from_str(json).unwrap();
+        assert_eq!(stage.run(), 1);
+    }
+
+    #[typetag::serialize]
+    trait Sink {
+        type Input: Clone;
+    }
+
+    #[typetag::serialize]
+    impl Sink for Counter {
+        type Input = u8;
+    }
+
+    #[test]
+    fn test_json_serialize_only() {
+        let trait_object = &Counter as &dyn Sink<Input = u8>;
+        let json = serde_json::to_string(trait_object).unwrap();
+        assert_eq!(json, r#"{"Counter":null}"#);
+    }
+
+    #[test]
+    fn test_separate_registries() {
+        let json = r#"{"Counter":null}"#;
+        assert!(serde_json::from_str::<Box<dyn Stage<Output = Frame>>>(json).is_err());
+
+        let frame = typetag::registry::<dyn Stage<Output = Frame>>().map(|tag| tag.name());
+        assert_eq!(frame.collect::<Vec<_>>(), ["Decoder"]);
+    }
+}
+
+mod macro_expanded {
+    use super::A;
+
+    #[typetag::serde]
+    trait Trait {}
+
+    macro_rules! impl_trait {
+        ($ty:ty) => {
+            #[typetag::serde]
+            impl Trait for $ty {}
+        };
+    }
+
+    impl_trait!(A);
+}
+
+// https://github.com/dtolnay/typetag/issues/28
+mod trait_hierarchy {
+    use serde::{Deserialize, Serialize};
+
+    #[typetag::serde]
+    pub trait Base {}
+
+    #[derive(Serialize, Deserialize)]
+    struct SomeBase;
+
+    #[typetag::serde]
+    impl Base for SomeBase {}
+
+    #[typetag::serde]
+    pub trait Derived: Base {}
+
+    #[derive(Serialize, Deserialize)]
+    struct SomeDerived;
+
+    #[typetag::serde]
+    impl Base for SomeDerived {}
+
+    #[typetag::serde]
+    impl Derived for SomeDerived {}
+}
+
+mod tag_mismatch {
+    use serde::Serialize;
+
+    #[typetag::serialize(tag = "type")]
+    trait Trait {}
+
+    #[derive(Serialize)]
+    struct Tagged<T> {
+        #[serde(rename = "type")]
+        t: T,
+    }
+
+    #[typetag::serialize]
+    impl<T: Serialize> Trait for Tagged<T> {}
+
+    #[test]
+    fn test_json_serialize() {
+        let trait_object = &Tagged { t: "Tagged" } as &dyn Trait;
+        let json = serde_json::to_string(trait_object).unwrap();
+        let expected = r#"{"type":"Tagged"}"#;
+        assert_eq!(json, expected);
+
+        let trait_object = &Tagged { t: "Mismatch" } as &dyn Trait;
+        let err = serde_json::to_string(trait_object).unwrap_err();
+        let expected = r#"mismatched value for tag "type": "Tagged" vs "Mismatch""#;
+        assert_eq!(err.to_string(), expected);
+
+        let trait_object = &Tagged { t: false } as &dyn Trait;
+        let err = serde_json::to_string(trait_object).unwrap_err();
+        let expected = r#"mismatched value for tag "type": "Tagged" vs non-string"#;
+        assert_eq!(err.to_string(), expected);
+    }
+}
+
+mod async_traits {
+    use async_trait::async_trait;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Serialize, Deserialize)]
+    pub struct InOrder;
+
+    #[derive(Serialize, Deserialize)]
+    pub struct OutOfOrder;
+
+    #[typetag::serde]
+    #[async_trait]
+    trait TypetagAsync {
+        async fn f(&self) {}
+    }
+
+    #[typetag::serde]
+    #[async_trait]
+    impl TypetagAsync for InOrder {
+        async fn f(&self) {}
+    }
+
+    #[async_trait]
+    #[typetag::serde]
+    impl TypetagAsync for OutOfOrder {
+        async fn f(&self) {}
+    }
+
+    #[async_trait]
+    #[typetag::serde]
+    trait AsyncTypetag {
+        async fn f(&self) {}
+    }
+
+    #[async_trait]
+    #[typetag::serde]
+    impl AsyncTypetag for InOrder {
+        async fn f(&self) {}
+    }
+
+    #[typetag::serde]
+    #[async_trait]
+    impl AsyncTypetag for OutOfOrder {
+        async fn f(&self) {}
+    }
+}
+
+mod registry {
+    use super::{A, B, C};
+
+    #[typetag::serde]
+    trait Trait {}
+
+    #[typetag::serde]
+    impl Trait for A {}
+
+    #[typetag::serde(name = "A")]
+    impl Trait for B {}
+
+    #[typetag::serde]
+    impl Trait for C {}
+
+    #[test]
+    fn test_registry() {
+        let tags: Vec<(&str, bool)> = typetag::registry::<dyn Trait>()
+            .map(|tag| (tag.name(), tag.is_duplicate()))
+            .collect();
+        assert_eq!(tags, [("A", true), ("C", false)]);
+    }
+}
+
+mod ids {
+    use super::{A, B};
+
+    #[typetag::serde]
+    trait Externally {
+        fn assert_a_is_11(&self);
+    }
+
+    #[typetag::serde(tag = "type")]
+    trait Internally {
+        fn assert_a_is_11(&self);
+    }
+
+    #[typetag::serde(tag = "type", content = "content")]
+    trait Adjacently {
+        fn assert_a_is_11(&self);
+    }
+
+    #[typetag::serde(id = 1)]
+    impl Externally for A {
+        fn assert_a_is_11(&self) {
+            assert_eq!(self.a, 11);
+        }
+    }
+
+    #[typetag::serde(name = "Bee", id = 2)]
+    impl Externally for B {
+        fn assert_a_is_11(&self) {
+            panic!("is not A!");
+        }
+    }
+
+    #[typetag::serde(id = 1)]
+    impl Internally for A {
+        fn assert_a_is_11(&self) {
+            assert_eq!(self.a, 11);
+        }
+    }
+
+    #[typetag::serde(id = 1)]
+    impl Adjacently for A {
+        fn assert_a_is_11(&self) {
+            assert_eq!(self.a, 11);
+        }
+    }
+
+    #[typetag::serde]
+    impl Internally for B {
+        fn assert_a_is_11(&self) {
+            panic!("is not A!");
+        }
+    }
+
+    #[test]
+    fn test_postcard_externally() {
+        let trait_object = &A { a: 11 } as &dyn Externally;
+        let bytes = postcard::to_stdvec(trait_object).unwrap();
+        assert_eq!(bytes, [1, 1, 11]);
+        let trait_object: Box<dyn Externally> = postcard::from_bytes(&bytes).unwrap();
+        trait_object.assert_a_is_11();
+    }
+
+    #[test]
+    fn test_postcard_internally() {
+        let trait_object = &A { a: 11 } as &dyn Internally;
+        let bytes = postcard::to_stdvec(trait_object).unwrap();
+        let trait_object: Box<dyn Internally> = postcard::from_bytes(&bytes).unwrap();
+        trait_object.assert_a_is_11();
+    }
+
+    #[test]
+    fn test_postcard_adjacently() {
+        let trait_object = &A { a: 11 } as &dyn Adjacently;
+        let bytes = postcard::to_stdvec(trait_object).unwrap();
+        assert_eq!(bytes, [1, 11]);
+        let trait_object: Box<dyn Adjacently> = postcard::from_bytes(&bytes).unwrap();
+        trait_object.assert_a_is_11();
+    }
+
+    #[test]
+    fn test_postcard_missing_id() {
+        let trait_object = &B { b: 11 } as &dyn Internally;
+        match postcard::to_stdvec(trait_object) {
+            Ok(_) => panic!("unexpectedly serialized an impl without an id"),
+            Err(err) => assert_eq!(err, postcard::Error::SerdeSerCustom),
+        }
+
+        let json = serde_json::to_string(trait_object).unwrap();
+        assert_eq!(json, r#"{"type":"B","b":11}"#);
+    }
+
+    #[test]
+    fn test_postcard_unknown_id() {
+        match postcard::from_bytes::<Box<dyn Externally>>(&[9, 11]) {
+            Ok(_) => panic!("unexpectedly deserialized an unknown id"),
+            Err(err) => assert_eq!(err, postcard::Error::SerdeDeCustom),
+        }
+    }
+
+    #[test]
+    fn test_json_uses_names() {
+        let trait_object = &B { b: 11 } as &dyn Externally;
+        let json = serde_json::to_string(trait_object).unwrap();
+        assert_eq!(json, r#"{"Bee":{"b":11}}"#);
+
+        let json = r#"{"A":{"a":11}}"#;
+        let trait_object: Box<dyn Externally> = serde_json::from_str(json).unwrap();
+        trait_object.assert_a_is_11();
+    }
+}
+
+mod untagged {
+    use serde::{Deserialize, Serialize};
+
+    #[typetag::serde(untagged)]
+    trait Shape {
+        fn area(&self) -> f64;
+    }
+
+    #[derive(Serialize, Deserialize)]
+    #[serde(deny_unknown_fields)]
+    struct Circle {
+        radius: f64,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    #[serde(deny_unknown_fields)]
+    struct Square {
+        side: f64,
+    }
+
+    #[typetag::serde]
+    impl Shape for Circle {
+        fn area(&self) -> f64 {
+            3.0 * self.radius * self.radius
+        }
+    }
+
+    #[typetag::serde]
+    impl Shape for Square {
+        fn area(&self) -> f64 {
+            self.side * self.side
+        }
+    }
+
+    #[test]
+    fn test_json_serialize() {
+        let trait_object = &Square { side: 2.0 } as &dyn Shape;
+        let json = serde_json::to_string(trait_object).unwrap();
+        assert_eq!(json, r#"{"side":2.0}"#);
+    }
+
+    #[test]
+    fn test_json_deserialize() {
+        let trait_object: Box<dyn Shape> = serde_json::from_str(r#"{"radius":1.0}"#).unwrap();
+        assert_eq!(trait_object.area(), 3.0);
+        let trait_object: Box<dyn Shape> = serde_json::from_str(r#"{"side":2.0}"#).unwrap();
+        assert_eq!(trait_object.area(), 4.0);
+    }
+
+    #[test]
+    fn test_json_deserialize_no_match() {
+        match serde_json::from_str::<Box<dyn Shape>>(r#"{"width":1.0}"#) {
+            Ok(_) => panic!("unexpectedly deserialized an untagged mismatch"),
+            Err(err) => {
+                let expected = "data did not match any impl of untagged dyn Shape: \
+                    Circle: unknown field `width`, expected `radius`; \
+                    Square: unknown field `width`, expected `side`";
+                assert_eq!(err.to_string(), expected);
+            }
+        }
+    }
+
+    #[typetag::serde(untagged)]
+    trait Event {
+        fn is_catchall(&self) -> bool;
+    }
+
+    #[derive(Serialize, Deserialize)]
+    struct Click {
+        x: i32,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    struct Catchall(serde_json::Value);
+
+    #[typetag::serde(priority = 1)]
+    impl Event for Click {
+        fn is_catchall(&self) -> bool {
+            false
+        }
+    }
+
+    #[typetag::serde]
+    impl Event for Catchall {
+        fn is_catchall(&self) -> bool {
+            true
+        }
+    }
+
+    #[test]
+    fn test_json_deserialize_priority() {
+        let trait_object: Box<dyn Event> = serde_json::from_str(r#"{"x":1}"#).unwrap();
+        assert!(!trait_object.is_catchall());
+        let trait_object: Box<dyn Event> = serde_json::from_str(r#"{"y":1}"#).unwrap();
+        assert!(trait_object.is_catchall());
+    }
+}
+
+mod enum_repr {
+    use super::{A, B};
+
+    #[typetag::serde(repr = "enum")]
+    trait Trait {
+        fn assert_a_is_11(&self);
+    }
+
+    #[typetag::serde(id = 1)]
+    impl Trait for A {
+        fn assert_a_is_11(&self) {
+            assert_eq!(self.a, 11);
+        }
+    }
+
+    #[typetag::serde(id = 2)]
+    impl Trait for B {
+        fn assert_a_is_11(&self) {
+            panic!("is not A!");
+        }
+    }
+
+    #[typetag::serde(repr = "enum")]
+    trait Unnumbered {
+        fn assert_a_is_11(&self);
+    }
+
+    #[typetag::serde]
+    impl Unnumbered for A {
+        fn assert_a_is_11(&self) {
+            assert_eq!(self.a, 11);
+        }
+    }
+
+    #[test]
+    fn test_ron_round_trip() {
+        let trait_object = &A { a: 11 } as &dyn Trait;
+        let ron = ron::to_string(trait_object).unwrap();
+        assert_eq!(ron, "A((a:11))");
+        let trait_object: Box<dyn Trait> = ron::from_str(&ron).unwrap();
+        trait_object.assert_a_is_11();
+    }
+
+    #[test]
+    fn test_ron_unknown_variant() {
+        match ron::from_str::<Box<dyn Trait>>("C((c:11))") {
+            Ok(_) => panic!("unexpectedly deserialized an unknown variant"),
+            Err(err) => {
+                let expected = "1:2: unknown variant `C`, expected `A` or `B`";
+                assert_eq!(err.to_string(), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        let trait_object = &A { a: 11 } as &dyn Trait;
+        let json = serde_json::to_string(trait_object).unwrap();
+        assert_eq!(json, r#"{"A":{"a":11}}"#);
+        let trait_object: Box<dyn Trait> = serde_json::from_str(&json).unwrap();
+        trait_object.assert_a_is_11();
+    }
+
+    #[test]
+    fn test_postcard_round_trip() {
+        let trait_object = &A { a: 11 } as &dyn Trait;
+        let bytes = postcard::to_stdvec(trait_object).unwrap();
+        assert_eq!(bytes, [1, 11]);
+        let trait_object: Box<dyn Trait> = postcard::from_bytes(&bytes).unwrap();
+        trait_object.assert_a_is_11();
+    }
+
+    #[test]
+    fn test_postcard_without_id() {
+        let trait_object = &A { a: 11 } as &dyn Unnumbered;
+        match postcard::to_stdvec(trait_object) {
+            Ok(_) => panic!("unexpectedly serialized an impl without an id"),
+            Err(err) => assert_eq!(err, postcard::Error::SerdeSerCustom),
+        }
+
+        let ron = ron::to_string(trait_object).unwrap();
+        assert_eq!(ron, "A((a:11))");
+    }
+}
+
+mod crate_path {
+    use super::{A, B};
+
+    // Generated code must not fall back to `typetag::` paths.
+    mod typetag {}
+
+    mod facade {
+        pub use ::typetag;
+    }
+
+    #[::typetag::serde(crate = "facade::typetag", tag = "type")]
+    trait Trait {
+        fn assert_a_is_11(&self);
+    }
+
+    #[::typetag::serde(crate = "facade::typetag")]
+    impl Trait for A {
+        fn assert_a_is_11(&self) {
+            assert_eq!(self.a, 11);
+        }
+    }
+
+    #[::typetag::serde(crate = "facade::typetag", registry = "manual")]
+    trait Manual {
+        fn assert_b_is_11(&self);
+    }
+
+    #[::typetag::serde(crate = "facade::typetag", registry = "manual")]
+    impl Manual for B {
+        fn assert_b_is_11(&self) {
+            assert_eq!(self.b, 11);
+        }
+    }
+
+    ::typetag::register!(crate = "facade::typetag", dyn Manual => [B]);
+
+    #[test]
+    fn test_json_round_trip() {
+        let trait_object = &A { a: 11 } as &dyn Trait;
+        let json = serde_json::to_string(trait_object).unwrap();
+        assert_eq!(json, r#"{"type":"A","a":11}"#);
+        let trait_object: Box<dyn Trait> = serde_json::from_str(&json).unwrap();
+        trait_object.assert_a_is_11();
+    }
+
+    #[test]
+    fn test_json_manual_registry() {
+        let json = r#"{"B":{"b":11}}"#;
+        let trait_object: Box<dyn Manual> = serde_json::from_str(json).unwrap();
+        trait_object.assert_b_is_11();
+    }
+}
+
+mod validate {
+    use super::{A, B, C};
+    use std::error::Error;
+
+    #[typetag::serde]
+    trait Trait {}
+
+    #[typetag::serde]
+    impl Trait for A {}
+
+    #[typetag::serde(name = "A")]
+    impl Trait for B {}
+
+    #[typetag::serde(alias = "A")]
+    impl Trait for C {}
+
+    #[typetag::serde]
+    trait Unique {}
+
+    #[typetag::serde]
+    impl Unique for A {}
+
+    #[typetag::serde(alias = "Old")]
+    impl Unique for B {}
+
+    #[typetag::serde]
+    trait Numbered {}
+
+    #[typetag::serde(id = 7)]
+    impl Numbered for A {}
+
+    #[typetag::serde(id = 7)]
+    impl Numbered for B {}
+
+    #[test]
+    fn test_validate() {
+        let err = typetag::validate::<dyn Trait>().unwrap_err();
+        let collisions = err.collisions();
+        assert_eq!(collisions.len(), 1);
+        assert_eq!(collisions[0].tag(), Some("A"));
+        assert_eq!(collisions[0].id(), None);
+
+        let mut origins: Vec<(&str, &str, &str)> = collisions[0]
+            .origins()
+            .iter()
+            .map(|origin| (origin.type_name(), origin.module_path(), origin.file()))
+            .collect();
+        origins.sort_unstable();
+        assert_eq!(
+            origins,
+            [
+                ("test::A", "test::validate", "tests/test.rs"),
+                ("test::B", "test::validate", "tests/test.rs"),
+                ("test::C", "test::validate", "tests/test.rs"),
+            ],
+        );
+        assert!(err
+            .to_string()
+            .starts_with("duplicate tags of dyn test::validate::Trait: `A` registered by `test::"));
+    }
+
+    #[test]
+    fn test_validate_ids() {
+        let err = typetag::validate::<dyn Numbered>().unwrap_err();
+        let collisions = err.collisions();
+        assert_eq!(collisions.len(), 1);
+        assert_eq!(collisions[0].tag(), None);
+        assert_eq!(collisions[0].id(), Some(7));
+        assert_eq!(collisions[0].origins().len(), 2);
+        assert!(err.to_string().starts_with(
+            "duplicate tags of dyn test::validate::Numbered: id 7 registered by `test::"
+        ));
+    }
+
+    #[test]
+    fn test_validate_unique() -> Result<(), Box<dyn Error>> {
+        typetag::validate::<dyn Unique>()?;
+        Ok(())
+    }
+}
+
+mod override_priority {
+    use super::{A, B, C};
+
+    #[typetag::serde]
+    trait Codec {
+        fn which(&self) -> char;
+    }
+
+    #[typetag::serde(name = "Gzip")]
+    impl Codec for A {
+        fn which(&self) -> char {
+            'A'
+        }
+    }
+
+    #[typetag::serde(name = "Gzip", priority = 10)]
+    impl Codec for B {
+        fn which(&self) -> char {
+            'B'
+        }
+    }
+
+    #[typetag::serde(alias = "Gzip")]
+    impl Codec for C {
+        fn which(&self) -> char {
+            'C'
+        }
+    }
+
+    #[typetag::serde]
+    trait Tied {}
+
+    #[typetag::serde(name = "Gzip", priority = 10)]
+    impl Tied for A {}
+
+    #[typetag::serde(name = "Gzip", priority = 10)]
+    impl Tied for B {}
+
+    #[typetag::serde(name = "Gzip")]
+    impl Tied for C {}
+
+    #[typetag::serde(untagged)]
+    trait Untagged {
+        fn which(&self) -> char;
+    }
+
+    #[typetag::serde(name = "Either")]
+    impl Untagged for A {
+        fn which(&self) -> char {
+            'A'
+        }
+    }
+
+    #[typetag::serde(name = "Either", priority = 10)]
+    impl Untagged for B {
+        fn which(&self) -> char {
+            'B'
+        }
+    }
+
+    #[test]
+    fn test_json_highest_priority_wins() {
+        let json = r#"{"Gzip":{"b":11}}"#;
+        let codec: Box<dyn Codec> = serde_json::from_str(json).unwrap();
+        assert_eq!(codec.which(), 'B');
+        typetag::validate::<dyn Codec>().unwrap();
+    }
+
+    #[test]
+    fn test_json_tie_is_non_unique() {
+        let json = r#"{"Gzip":{"a":11}}"#;
+        let err = match serde_json::from_str::<Box<dyn Tied>>(json) {
+            Ok(_) => panic!("unexpectedly deserialized {}", json),
+            Err(err) => err,
+        };
+        assert_eq!(
+            err.to_string(),
+            "non-unique tag of dyn Tied: \"Gzip\" at line 1 column 7",
+        );
+
+        let err = typetag::validate::<dyn Tied>().unwrap_err();
+        let collisions = err.collisions();
+        assert_eq!(collisions.len(), 1);
+        let mut origins: Vec<&str> = collisions[0]
+            .origins()
+            .iter()
+            .map(|origin| origin.type_name())
+            .collect();
+        origins.sort_unstable();
+        assert_eq!(origins, ["test::A", "test::B"]);
+    }
+
+    #[test]
+    fn test_json_untagged_tries_highest_priority_first() {
+        let json = r#"{"a":11,"b":11}"#;
+        let trait_object: Box<dyn Untagged> = serde_json::from_str(json).unwrap();
+        assert_eq!(trait_object.which(), 'B');
+
+        let json = r#"{"a":11}"#;
+        let trait_object: Box<dyn Untagged> = serde_json::from_str(json).unwrap();
+        assert_eq!(trait_object.which(), 'A');
+
+        // The same priority gives the shared name to B alone.
+        typetag::validate::<dyn Untagged>().unwrap();
+    }
+}
+
+mod with {
+    use super::{A, B};
+    use serde::{Deserialize, Serialize};
+
+    #[typetag::serde]
+    trait Trait: Send {
+        fn assert_a_is_11(&self);
+    }
+
+    #[typetag::serde]
+    impl Trait for A {
+        fn assert_a_is_11(&self) {
+            assert_eq!(self.a, 11);
+        }
+    }
+
+    #[typetag::serde]
+    impl Trait for B {
+        fn assert_a_is_11(&self) {
+            panic!("is not A!");
+        }
+    }
+
+    struct Kind;
+
+    impl typetag::with::Key for Kind {
+        const NAME: &'static str = "kind";
+    }
+
+    struct Value;
+
+    impl typetag::with::Key for Value {
+        const NAME: &'static str = "value";
+    }
+
+    #[derive(Serialize, Deserialize)]
+    struct Reprs {
+        #[serde(with = "typetag::with::Externally")]
+        externally: Box<dyn Trait>,
+        #[serde(with = "typetag::with::Internally::<Kind>")]
+        internally: Box<dyn Trait + Send>,
+        #[serde(with = "typetag::with::Adjacently::<Kind, Value>")]
+        adjacently: Box<dyn Trait>,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    struct Module {
+        #[serde(with = "typetag::with::externally")]
+        externally: Box<dyn Trait>,
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        let reprs = Reprs {
+            externally: Box::new(A { a: 11 }),
+            internally: Box::new(A { a: 11 }),
+            adjacently: Box::new(A { a: 11 }),
+        };
+        let json = serde_json::to_string(&reprs).unwrap();
+        let expected = r#"{"externally":{"A":{"a":11}},"internally":{"kind":"A","a":11},"adjacently":{"kind":"A","value":{"a":11}}}"#;
+        assert_eq!(json, expected);
+
+        let reprs: Reprs = serde_json::from_str(&json).unwrap();
+        reprs.externally.assert_a_is_11();
+        reprs.internally.assert_a_is_11();
+        reprs.adjacently.assert_a_is_11();
+    }
+
+    #[test]
+    fn test_json_externally_module() {
+        let module = Module {
+            externally: Box::new(A { a: 11 }),
+        };
+        let json = serde_json::to_string(&module).unwrap();
+        assert_eq!(json, r#"{"externally":{"A":{"a":11}}}"#);
+
+        let module: Module = serde_json::from_str(&json).unwrap();
+        module.externally.assert_a_is_11();
+    }
+
+    #[test]
+    fn test_trait_repr_unchanged() {
+        let trait_object = &B { b: 11 } as &dyn Trait;
+        let json = serde_json::to_string(trait_object).unwrap();
+        assert_eq!(json, r#"{"B":{"b":11}}"#);
+    }
+
+    #[test]
+    fn test_unknown_tag() {
+        let json = r#"{"externally":{"A":{"a":11}},"internally":{"kind":"C"},"adjacently":{"kind":"A","value":{"a":11}}}"#;
+        match serde_json::from_str::<Reprs>(json) {
+            Ok(_) => panic!("unexpectedly deserialized an unknown tag"),
+            Err(err) => assert_eq!(
+                err.to_string(),
+                "unknown variant `C`, expected `A` or `B` at line 1 column 53",
+            ),
+        }
+    }
+}
+
+mod error_context {
+    use serde::{Deserialize, Serialize};
+
+    #[typetag::serde]
+    trait WebEvent {}
+
+    #[typetag::serde(tag = "type")]
+    trait Shape {}
+
+    #[derive(Serialize, Deserialize)]
+    struct Click {
+        x: u8,
+    }
+
+    #[typetag::serde]
+    impl WebEvent for Click {}
+
+    #[derive(Serialize, Deserialize)]
+    struct Draw {
+        shape: Box<dyn Shape>,
+    }
+
+    #[typetag::serde]
+    impl WebEvent for Draw {}
+
+    #[derive(Serialize, Deserialize)]
+    struct Circle {
+        radius: u8,
+    }
+
+    #[typetag::serde]
+    impl Shape for Circle {}
+
+    fn error<T: for<'de> Deserialize<'de>>(json: &str) -> String {
+        match serde_json::from_str::<T>(json) {
+            Ok(_) => panic!("unexpectedly deserialized {}", json),
+            Err(err) => err.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_json_missing_field() {
+        let json = r#"{"Click":{}}"#;
+        let expected =
+            "while deserializing `Click` as dyn WebEvent: missing field `x` at line 1 column 11";
+        assert_eq!(error::<Box<dyn WebEvent>>(json), expected);
+    }
+
+    #[test]
+    fn test_json_internally_tagged() {
+        let json = r#"{"type":"Circle","radius":-1}"#;
+        let expected = "while deserializing `Circle` as dyn Shape: invalid value: integer `-1`, expected u8 at line 1 column 28";
+        assert_eq!(error::<Box<dyn Shape>>(json), expected);
+    }
+
+    #[test]
+    fn test_json_nested() {
+        let json = "[\n{\"Click\":{\"x\":1}},\n{\"Draw\":{\"shape\":{\"type\":\"Circle\"}}}]";
+        let expected = "while deserializing `Draw` as dyn WebEvent: while deserializing `Circle` as dyn Shape: missing field `radius` at line 3 column 34";
+        assert_eq!(error::<Vec<Box<dyn WebEvent>>>(json), expected);
+    }
+}
+
+mod suggestions {
+    use serde::{Deserialize, Serialize};
+
+    #[typetag::serde]
+    trait WebEvent {}
+
+    #[typetag::serde(no_suggestions)]
+    trait Quiet {}
+
+    #[typetag::serde]
+    trait Crowded {}
+
+    #[derive(Serialize, Deserialize)]
+    struct Click;
+
+    #[derive(Serialize, Deserialize)]
+    struct Clock;
+
+    #[derive(Serialize, Deserialize)]
+    struct PageLoad;
+
+    #[typetag::serde]
+    impl WebEvent for Click {}
+
+    #[typetag::serde]
+    impl WebEvent for Clock {}
+
+    #[typetag::serde]
+    impl WebEvent for PageLoad {}
+
+    #[typetag::serde]
+    impl Quiet for Click {}
+
+    #[typetag::serde]
+    impl Quiet for PageLoad {}
+
+    macro_rules! crowded {
+        ($($name:ident)*) => {
+            $(
+                #[derive(Serialize, Deserialize)]
+                struct $name;
+
+                #[typetag::serde]
+                impl Crowded for $name {}
+            )*
+        };
+    }
+
+    crowded!(T00 T01 T02 T03 T04 T05 T06 T07 T08 T09 T10 T11 T12 T13 T14 T15 T16 T17 T18 T19);
+
+    fn error<T: for<'de> Deserialize<'de>>(json: &str) -> String {
+        match serde_json::from_str::<T>(json) {
+            Ok(_) => panic!("unexpectedly deserialized {}", json),
+            Err(err) => err.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_json_single_suggestion() {
+        let expected = "unknown variant `Pageload`, did you mean `PageLoad`? at line 1 column 11";
+        assert_eq!(error::<Box<dyn WebEvent>>(r#"{"Pageload":null}"#), expected);
+    }
+
+    #[test]
+    fn test_json_several_suggestions() {
+        let expected =
+            "unknown variant `Clck`, did you mean one of `Click`, `Clock`? at line 1 column 7";
+        assert_eq!(error::<Box<dyn WebEvent>>(r#"{"Clck":null}"#), expected);
+    }
+
+    #[test]
+    fn test_json_no_close_match() {
+        let expected = "unknown variant `Scroll`, expected one of `Click`, `Clock`, `PageLoad` at line 1 column 9";
+        assert_eq!(error::<Box<dyn WebEvent>>(r#"{"Scroll":null}"#), expected);
+    }
+
+    #[test]
+    fn test_json_no_suggestions() {
+        let expected = "unknown variant `Clik`, expected `Click` or `PageLoad` at line 1 column 7";
+        assert_eq!(error::<Box<dyn Quiet>>(r#"{"Clik":null}"#), expected);
+    }
+
+    #[test]
+    fn test_json_capped_expected_list() {
+        let expected = "unknown variant `Scroll`, expected one of `T00`, `T01`, `T02`, `T03`, `T04`, `T05`, `T06`, `T07`, `T08`, `T09`, `T10`, `T11`, `T12`, `T13`, `T14`, `T15`, and 4 more at line 1 column 9";
+        assert_eq!(error::<Box<dyn Crowded>>(r#"{"Scroll":null}"#), expected);
+    }
+}
+
+mod unit_shorthand {
+    use serde::{Deserialize, Serialize};
+
+    #[typetag::serde(unit_shorthand)]
+    trait Externally {
+        fn is_page_load(&self) -> bool;
+    }
+
+    #[typetag::serde(tag = "type", unit_shorthand)]
+    trait Internally {
+        fn is_page_load(&self) -> bool;
+    }
+
+    #[typetag::serde(tag = "type", content = "content", unit_shorthand)]
+    trait Adjacently {
+        fn is_page_load(&self) -> bool;
+    }
+
+    #[typetag::serde]
+    trait Longhand {}
+
+    #[derive(Serialize, Deserialize)]
+    struct PageLoad;
+
+    #[derive(Serialize, Deserialize)]
+    struct Click {
+        x: u8,
+    }
+
+    macro_rules! impls {
+        ($($trait:ident)*) => {
+            $(
+                #[typetag::serde]
+                impl $trait for PageLoad {
+                    fn is_page_load(&self) -> bool {
+                        true
+                    }
+                }
+
+                #[typetag::serde]
+                impl $trait for Click {
+                    fn is_page_load(&self) -> bool {
+                        false
+                    }
+                }
+            )*
+        };
+    }
+
+    impls!(Externally Internally Adjacently);
+
+    #[typetag::serde]
+    impl Longhand for PageLoad {}
+
+    #[test]
+    fn test_json_shorthand() {
+        let json = r#""PageLoad""#;
+        let trait_object: Box<dyn Externally> = serde_json::from_str(json).unwrap();
+        assert!(trait_object.is_page_load());
+        let trait_object: Box<dyn Internally> = serde_json::from_str(json).unwrap();
+        assert!(trait_object.is_page_load());
+        let trait_object: Box<dyn Adjacently> = serde_json::from_str(json).unwrap();
+        assert!(trait_object.is_page_load());
+    }
+
+    #[test]
+    fn test_json_longhand_still_accepted() {
+        let json = r#"{"Click":{"x":1}}"#;
+        let trait_object: Box<dyn Externally> = serde_json::from_str(json).unwrap();
+        assert!(!trait_object.is_page_load());
+        let json = r#"{"type":"PageLoad"}"#;
+        let trait_object: Box<dyn Internally> = serde_json::from_str(json).unwrap();
+        assert!(trait_object.is_page_load());
+        let json = r#"{"type":"Click","content":{"x":1}}"#;
+        let trait_object: Box<dyn Adjacently> = serde_json::from_str(json).unwrap();
+        assert!(!trait_object.is_page_load());
+    }
+
+    #[test]
+    fn test_json_serialize_longhand() {
+        let trait_object = &PageLoad as &dyn Externally;
+        let json = serde_json::to_string(trait_object).unwrap();
+        assert_eq!(json, r#"{"PageLoad":null}"#);
+    }
+
+    #[test]
+    fn test_json_shorthand_with_data() {
+        match serde_json::from_str::<Box<dyn Internally>>(r#""Click""#) {
+            Ok(_) => panic!("unexpectedly deserialized a struct from a bare tag"),
+            Err(err) => {
+                let expected = "while deserializing `Click` as dyn Internally: invalid type: null, expected struct Click at line 1 column 7";
+                assert_eq!(err.to_string(), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn test_json_shorthand_not_enabled() {
+        match serde_json::from_str::<Box<dyn Longhand>>(r#""PageLoad""#) {
+            Ok(_) => panic!("unexpectedly deserialized a bare tag"),
+            Err(err) => {
+                let expected =
+                    "invalid type: string \"PageLoad\", expected dyn Longhand at line 1 column 10";
+                assert_eq!(err.to_string(), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn test_postcard_round_trip() {
+        let trait_object = &PageLoad as &dyn Adjacently;
+        let bytes = postcard::to_stdvec(trait_object).unwrap();
+        let trait_object: Box<dyn Adjacently> = postcard::from_bytes(&bytes).unwrap();
+        assert!(trait_object.is_page_load());
+    }
+}
+
+mod filtered {
+    use serde::{Deserialize, Serialize};
+    use typetag::{Filtered, Policy};
+
+    #[typetag::serde]
+    trait Action {
+        fn is_like(&self) -> bool;
+    }
+
+    #[typetag::serde(tag = "type")]
+    trait Internal: Send {}
+
+    #[typetag::serde(untagged)]
+    trait Untagged {
+        fn is_like(&self) -> bool;
+    }
+
+    #[derive(Serialize, Deserialize)]
+    struct Like;
+
+    #[derive(Serialize, Deserialize)]
+    struct DeleteAccount;
+
+    #[typetag::serde]
+    impl Action for Like {
+        fn is_like(&self) -> bool {
+            true
+        }
+    }
+
+    #[typetag::serde(alias = "Delete")]
+    impl Action for DeleteAccount {
+        fn is_like(&self) -> bool {
+            false
+        }
+    }
+
+    #[typetag::serde]
+    impl Internal for Like {}
+
+    #[typetag::serde]
+    impl Internal for DeleteAccount {}
+
+    #[typetag::serde(priority = 1)]
+    impl Untagged for DeleteAccount {
+        fn is_like(&self) -> bool {
+            false
+        }
+    }
+
+    #[typetag::serde]
+    impl Untagged for Like {
+        fn is_like(&self) -> bool {
+            true
+        }
+    }
+
+    struct Public;
+
+    impl Policy for Public {
+        fn allows(tag: &str) -> bool {
+            tag == "Like"
+        }
+    }
+
+    fn error<T: for<'de> Deserialize<'de>>(json: &str) -> String {
+        match serde_json::from_str::<T>(json) {
+            Ok(_) => panic!("unexpectedly deserialized {}", json),
+            Err(err) => err.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_json_allowed() {
+        let json = r#"{"Like":null}"#;
+        let action: Filtered<dyn Action, Public> = serde_json::from_str(json).unwrap();
+        assert!(action.is_like());
+        assert_eq!(serde_json::to_string(&action).unwrap(), json);
+    }
+
+    #[test]
+    fn test_json_disallowed() {
+        let expected = r#"disallowed tag of dyn Action: "DeleteAccount" at line 1 column 16"#;
+        let json = r#"{"DeleteAccount":null}"#;
+        assert_eq!(error::<Filtered<dyn Action, Public>>(json), expected);
+
+        let action: Box<dyn Action> = serde_json::from_str(json).unwrap();
+        assert!(!action.is_like());
+    }
+
+    #[test]
+    fn test_json_disallowed_alias() {
+        let expected = r#"disallowed tag of dyn Action: "DeleteAccount" at line 1 column 9"#;
+        assert_eq!(
+            error::<Filtered<dyn Action, Public>>(r#"{"Delete":null}"#),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_json_internally_tagged() {
+        let json = r#"{"type":"Like"}"#;
+        serde_json::from_str::<Filtered<dyn Internal + Send, Public>>(json).unwrap();
+
+        let expected = r#"disallowed tag of dyn Internal: "DeleteAccount" at line 1 column 23"#;
+        let json = r#"{"type":"DeleteAccount"}"#;
+        assert_eq!(error::<Filtered<dyn Internal, Public>>(json), expected);
+    }
+
+    #[test]
+    fn test_json_untagged_skips_disallowed() {
+        let action: Box<dyn Untagged> = serde_json::from_str("null").unwrap();
+        assert!(!action.is_like());
+        let action: Filtered<dyn Untagged, Public> = serde_json::from_str("null").unwrap();
+        assert!(action.is_like());
+    }
+}
+
+mod limits {
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Serialize, Deserialize)]
+    struct Blob {
+        #[serde(default)]
+        text: String,
+        #[serde(default)]
+        value: serde_json::Value,
+    }
+
+    #[typetag::serde(tag = "type", limits(bytes = 16, elements = 8, depth = 2))]
+    trait Internally {}
+
+    #[typetag::serde(tag = "type", limits(depth = 0))]
+    trait Shallow {}
+
+    #[typetag::serde(tag = "type", content = "content", limits(bytes = 16))]
+    trait Adjacently {}
+
+    #[typetag::serde(untagged, limits(elements = 4))]
+    trait Untagged {}
+
+    #[typetag::serde(unknown = typetag::Unknown, limits(bytes = 16, depth = 2))]
+    trait Fallback {}
+
+    #[typetag::serde]
+    impl Internally for Blob {}
+
+    #[typetag::serde]
+    impl Shallow for Blob {}
+
+    #[typetag::serde]
+    impl Adjacently for Blob {}
+
+    #[typetag::serde]
+    impl Untagged for Blob {}
+
+    #[typetag::serde]
+    impl Fallback for Blob {}
+
+    #[typetag::serde(unknown)]
+    impl Fallback for typetag::Unknown {}
+
+    fn error<T: for<'de> Deserialize<'de>>(json: &str) -> String {
+        match serde_json::from_str::<T>(json) {
+            Ok(_) => panic!("unexpectedly deserialized {}", json),
+            Err(err) => err.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_json_tag_first_is_not_buffered() {
+        let json = r#"{"type":"Blob","text":"far more than sixteen bytes","value":[[[[1,2,3,4,5,6,7,8,9]]]]}"#;
+        serde_json::from_str::<Box<dyn Internally>>(json).unwrap();
+    }
+
+    #[test]
+    fn test_json_tag_first_without_depth() {
+        let json = r#"{"type":"Blob","text":"short"}"#;
+        serde_json::from_str::<Box<dyn Shallow>>(json).unwrap();
+
+        let json = r#"{"text":"short","type":"Blob"}"#;
+        let expected = "buffered content of dyn Shallow exceeds the limit of 0 levels of nesting at line 1 column 7";
+        assert_eq!(error::<Box<dyn Shallow>>(json), expected);
+    }
+
+    #[test]
+    fn test_json_within_limits() {
+        let json = r#"{"text":"short","value":[1,2],"type":"Blob"}"#;
+        serde_json::from_str::<Box<dyn Internally>>(json).unwrap();
+    }
+
+    #[test]
+    fn test_json_bytes() {
+        let json = r#"{"text":"far more than sixteen bytes","type":"Blob"}"#;
+        let expected =
+            "buffered content of dyn Internally exceeds the limit of 16 bytes at line 1 column 37";
+        assert_eq!(error::<Box<dyn Internally>>(json), expected);
+    }
+
+    #[test]
+    fn test_json_elements() {
+        // The key counts as one element, like the keys of a buffered map.
+        let json = r#"{"value":[1,2,3,4,5,6,7,8,9],"type":"Blob"}"#;
+        let expected = "buffered content of dyn Internally exceeds the limit of 8 elements at line 1 column 26";
+        assert_eq!(error::<Box<dyn Internally>>(json), expected);
+    }
+
+    #[test]
+    fn test_json_depth() {
+        let json = r#"{"value":[[1]],"type":"Blob"}"#;
+        let expected = "buffered content of dyn Internally exceeds the limit of 2 levels of nesting at line 1 column 11";
+        assert_eq!(error::<Box<dyn Internally>>(json), expected);
+    }
+
+    #[test]
+    fn test_json_adjacently_content_first() {
+        let json = r#"{"content":{"text":"far more than sixteen bytes"},"type":"Blob"}"#;
+        let expected =
+            "buffered content of dyn Adjacently exceeds the limit of 16 bytes at line 1 column 48";
+        assert_eq!(error::<Box<dyn Adjacently>>(json), expected);
+
+        let json = r#"{"type":"Blob","content":{"text":"far more than sixteen bytes"}}"#;
+        serde_json::from_str::<Box<dyn Adjacently>>(json).unwrap();
+    }
+
+    #[test]
+    fn test_json_untagged() {
+        serde_json::from_str::<Box<dyn Untagged>>(r#"{"value":[1]}"#).unwrap();
+
+        let json = r#"{"value":[1,2,3]}"#;
+        let expected =
+            "buffered content of dyn Untagged exceeds the limit of 4 elements at line 1 column 17";
+        assert_eq!(error::<Box<dyn Untagged>>(json), expected);
+    }
+
+    #[test]
+    fn test_json_unknown_tag() {
+        serde_json::from_str::<Box<dyn Fallback>>(r#"{"Other":{"text":"short"}}"#).unwrap();
+
+        let json = r#"{"Other":{"text":"far more than sixteen bytes"}}"#;
+        let expected =
+            "buffered content of dyn Fallback exceeds the limit of 16 bytes at line 1 column 46";
+        assert_eq!(error::<Box<dyn Fallback>>(json), expected);
+
+        let json = r#"{"Other":[[[1]]]}"#;
+        let expected = "buffered content of dyn Fallback exceeds the limit of 2 levels of nesting at line 1 column 12";
+        assert_eq!(error::<Box<dyn Fallback>>(json), expected);
+    }
+}
+
+mod manual_registry {
+    use super::{A, B};
+
+    #[typetag::serde(registry = "manual")]
+    trait Trait {
+        fn assert_a_is_11(&self);
+    }
+
+    #[typetag::serde(registry = "manual")]
+    impl Trait for A {
+        fn assert_a_is_11(&self) {
+            assert_eq!(self.a, 11);
+        }
+    }
+
+    #[typetag::serde(registry = "manual")]
+    impl Trait for B {
+        fn assert_a_is_11(&self) {
+            panic!("is not A!");
+        }
+    }
+
+    typetag::register!(dyn Trait => [A]);
+
+    #[test]
+    fn test_json_deserialize() {
+        let json = r#"{"A":{"a":11}}"#;
+        let trait_object: Box<dyn Trait> = serde_json::from_str(json).unwrap();
+        trait_object.assert_a_is_11();
+    }
+
+    #[test]
+    fn test_json_deserialize_unregistered() {
+        let json = r#"{"B":{"b":11}}"#;
+        match serde_json::from_str::<Box<dyn Trait>>(json) {
+            Ok(_) => panic!("unexpectedly deserialized an unregistered impl"),
+            Err(err) => {
+                let expected = "unknown variant `B`, expected `A` at line 1 column 4";
+                assert_eq!(err.to_string(), expected);
+            }
+        }
+    }
+}
+
+mod recursion {
+    use serde::{Deserialize, Serialize};
+
+    #[typetag::serde(limits(recursion = 4))]
+    trait Externally {}
+
+    #[typetag::serde(tag = "type", limits(recursion = 4))]
+    trait Internally {}
+
+    #[typetag::serde(tag = "type", content = "content", limits(recursion = 4))]
+    trait Adjacently {}
+
+    #[typetag::serde]
+    trait Unlimited {}
+
+    #[derive(Serialize, Deserialize)]
+    struct Leaf;
+
+    #[derive(Serialize, Deserialize)]
+    struct ExternallyNode {
+        child: Box<dyn Externally>,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    struct InternallyNode {
+        child: Box<dyn Internally>,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    struct AdjacentlyNode {
+        child: Box<dyn Adjacently>,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    struct UnlimitedNode {
+        child: Box<dyn Unlimited>,
+    }
+
+    #[typetag::serde]
+    impl Externally for Leaf {}
+
+    #[typetag::serde(name = "Node")]
+    impl Externally for ExternallyNode {}
+
+    #[typetag::serde]
+    impl Internally for Leaf {}
+
+    #[typetag::serde(name = "Node")]
+    impl Internally for InternallyNode {}
+
+    #[typetag::serde]
+    impl Adjacently for Leaf {}
+
+    #[typetag::serde(name = "Node")]
+    impl Adjacently for AdjacentlyNode {}
+
+    #[typetag::serde(name = "Node")]
+    impl Unlimited for UnlimitedNode {}
+
+    #[typetag::serde(name = "Externally")]
+    impl Unlimited for ExternallyNode {}
+
+    fn externally(nodes: usize) -> String {
+        let mut json = r#"{"Leaf":null}"#.to_owned();
+        for _ in 0..nodes {
+            json = format!(r#"{{"Node":{{"child":{}}}}}"#, json);
+        }
+        json
+    }
+
+    fn internally(nodes: usize) -> String {
+        let mut json = r#"{"type":"Leaf"}"#.to_owned();
+        for _ in 0..nodes {
+            json = format!(r#"{{"type":"Node","child":{}}}"#, json);
+        }
+        json
+    }
+
+    fn adjacently(nodes: usize) -> String {
+        let mut json = r#"{"type":"Leaf"}"#.to_owned();
+        for _ in 0..nodes {
+            json = format!(r#"{{"type":"Node","content":{{"child":{}}}}}"#, json);
+        }
+        json
+    }
+
+    fn error<T: for<'de> Deserialize<'de>>(json: &str) -> String {
+        match serde_json::from_str::<T>(json) {
+            Ok(_) => panic!("unexpectedly deserialized {}", json),
+            Err(err) => err.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_externally() {
+        serde_json::from_str::<Box<dyn Externally>>(&externally(3)).unwrap();
+        let err = error::<Box<dyn Externally>>(&externally(4));
+        assert!(err.contains("dyn Externally exceeds the limit of 4 nested trait objects"));
+    }
+
+    #[test]
+    fn test_internally() {
+        serde_json::from_str::<Box<dyn Internally>>(&internally(3)).unwrap();
+        let err = error::<Box<dyn Internally>>(&internally(4));
+        assert!(err.contains("dyn Internally exceeds the limit of 4 nested trait objects"));
+    }
+
+    #[test]
+    fn test_adjacently() {
+        serde_json::from_str::<Box<dyn Adjacently>>(&adjacently(3)).unwrap();
+        let err = error::<Box<dyn Adjacently>>(&adjacently(4));
+        assert!(err.contains("dyn Adjacently exceeds the limit of 4 nested trait objects"));
+    }
+
+    #[test]
+    fn test_unlimited_not_counted() {
+        let mut json = format!(r#"{{"Externally":{{"child":{}}}}}"#, externally(2));
+        for _ in 0..10 {
+            json = format!(r#"{{"Node":{{"child":{}}}}}"#, json);
+        }
+        serde_json::from_str::<Box<dyn Unlimited>>(&json).unwrap();
+    }
+
+    #[test]
+    fn test_depth_restored_after_error() {
+        for _ in 0..10 {
+            error::<Box<dyn Internally>>(&internally(4));
+        }
+        serde_json::from_str::<Box<dyn Internally>>(&internally(3)).unwrap();
     }
 }
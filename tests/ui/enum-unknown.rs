@@ -0,0 +1,4 @@
+#[typetag::serde(repr = "enum", unknown = typetag::Unknown)]
+pub trait Trait {}
+
+fn main() {}
@@ -0,0 +1,15 @@
+use serde::{Deserialize, Serialize};
+
+#[typetag::serde]
+pub trait Trait {}
+
+#[derive(Serialize, Deserialize)]
+pub struct Wrapper<T>(T);
+
+#[derive(Serialize, Deserialize)]
+pub struct Other<T>(T);
+
+#[typetag::serde(instances(Other<u8> = "OtherU8"))]
+impl<T: Serialize> Trait for Wrapper<T> {}
+
+fn main() {}
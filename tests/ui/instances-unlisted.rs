@@ -0,0 +1,16 @@
+use serde::{Deserialize, Serialize};
+
+#[typetag::serde]
+trait Trait {}
+
+#[derive(Serialize, Deserialize)]
+struct Wrapper<T> {
+    value: T,
+}
+
+#[typetag::serde(instances(Wrapper<u8> = "WrapperU8"))]
+impl<T: Serialize> Trait for Wrapper<T> {}
+
+fn main() {
+    let _ = &Wrapper { value: 0u16 } as &dyn Trait;
+}
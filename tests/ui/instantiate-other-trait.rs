@@ -0,0 +1,4 @@
+#[typetag::serde(instantiate(Other<u8>))]
+pub trait Trait<T> {}
+
+fn main() {}
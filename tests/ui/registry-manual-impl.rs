@@ -0,0 +1,12 @@
+use serde::{Deserialize, Serialize};
+
+#[typetag::serde]
+trait Trait {}
+
+#[derive(Serialize, Deserialize)]
+struct Struct;
+
+#[typetag::serde(registry = "manual")]
+impl Trait for Struct {}
+
+fn main() {}
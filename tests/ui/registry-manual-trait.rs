@@ -0,0 +1,14 @@
+use serde::{Deserialize, Serialize};
+
+#[typetag::serde(registry = "manual")]
+trait Trait {}
+
+#[derive(Serialize, Deserialize)]
+struct Struct;
+
+#[typetag::serde]
+impl Trait for Struct {}
+
+typetag::register!(dyn Trait => [Struct]);
+
+fn main() {}
@@ -0,0 +1,4 @@
+#[typetag::serde(namespace = "package")]
+pub trait Trait {}
+
+fn main() {}
@@ -0,0 +1,4 @@
+#[typetag::serde(registry = "linker")]
+pub trait Trait {}
+
+fn main() {}
@@ -0,0 +1,4 @@
+#[typetag::serde(untagged, unit_shorthand)]
+pub trait Trait {}
+
+fn main() {}
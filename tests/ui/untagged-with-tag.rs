@@ -0,0 +1,4 @@
+#[typetag::serde(untagged, tag = "type")]
+pub trait Trait {}
+
+fn main() {}